@@ -1,20 +1,127 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr {
+    Named(String),
+    Generic {
+        name: String,
+        arguments: Vec<TypeExpr>,
+    },
+    Optional(Box<TypeExpr>),
+    Array(Box<TypeExpr>),
+    Function {
+        params: Vec<TypeExpr>,
+        return_type: Box<TypeExpr>,
+    },
+    Tuple(Vec<TypeExpr>),
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeExpr::Named(name) => write!(f, "{}", name),
+            TypeExpr::Generic { name, arguments } => write!(f, "{}<{}>", name, join_types(arguments)),
+            TypeExpr::Optional(inner) => write!(f, "{}?", inner),
+            TypeExpr::Array(element) => write!(f, "[{}]", element),
+            TypeExpr::Function { params, return_type } => write!(f, "({}) -> {}", join_types(params), return_type),
+            TypeExpr::Tuple(elements) if elements.len() == 1 => write!(f, "({},)", elements[0]),
+            TypeExpr::Tuple(elements) => write!(f, "({})", join_types(elements)),
+        }
+    }
+}
+
+fn join_types(types: &[TypeExpr]) -> String {
+    types.iter().map(|type_expr| type_expr.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeParameter {
+    pub name: String,
+    pub bounds: Vec<TypeExpr>,
+}
+
 #[derive(Debug)]
 pub enum ASTNode {
     Program(Vec<ASTNode>),
-    UseStatement(String),
-    ReturnStatement(Box<ASTNode>),
+    UseStatement {
+        visibility: Visibility,
+        imports: Vec<UseImport>,
+    },
+    TempoDeclaration(Box<ASTNode>),
+    MeterDeclaration {
+        beats: String,
+        beat_unit: String,
+    },
+    KeyDeclaration {
+        tonic: String,
+        mode: String,
+    },
+    WithContext {
+        settings: Vec<ASTNode>,
+        body: Vec<ASTNode>,
+    },
+    ReturnStatement(Option<Box<ASTNode>>),
+    WaitStatement(Box<ASTNode>),
+    EveryLoop {
+        interval: Box<ASTNode>,
+        body: Vec<ASTNode>,
+    },
     VariableDeclaration {
-        var_type: String,
+        attributes: Vec<Attribute>,
+        mutable: bool,
+        var_type: TypeExpr,
         name: String,
         value: Box<ASTNode>,
     },
+    ConstDeclaration {
+        attributes: Vec<Attribute>,
+        visibility: Visibility,
+        var_type: TypeExpr,
+        name: String,
+        value: Box<ASTNode>,
+    },
+    TypeAlias {
+        name: String,
+        type_params: Vec<TypeParameter>,
+        target: TypeExpr,
+    },
     FunctionDeclaration {
+        attributes: Vec<Attribute>,
         name: String,
-        params: Vec<(String, String)>,
-        return_type: String,
+        type_params: Vec<TypeParameter>,
+        params: Vec<Parameter>,
+        return_type: TypeExpr,
+        body: Vec<ASTNode>,
+    },
+    Lambda {
+        params: Vec<Parameter>,
+        // `None` for `=> expr` lambdas without a declared type
+        return_type: Option<TypeExpr>,
         body: Vec<ASTNode>,
+        captures: Vec<String>,
     },
     Literal(String),
+    ChordLiteral(Vec<ASTNode>),
+    ChordSymbol {
+        root: String,
+        quality: String,
+    },
+    ScaleLiteral {
+        tonic: String,
+        mode: String,
+    },
+    Spawn(Vec<ASTNode>),
+    Await(Box<ASTNode>),
+    Quantity {
+        value: String,
+        unit: String,
+    },
     Identifier(String),
     ArrayLiteral(Vec<ASTNode>),
     ObjectLiteral(Vec<(String, ASTNode)>),
@@ -22,20 +129,41 @@ pub enum ASTNode {
         object: Box<ASTNode>,
         property: String,
     },
+    FunctionReference(String),
+    NamedArgument {
+        name: String,
+        value: Box<ASTNode>,
+    },
     FunctionCall {
-        function: String,
+        callee: Box<ASTNode>,
+        type_arguments: Vec<TypeExpr>,
         arguments: Vec<ASTNode>,
     },
     BinaryExpression {
         left: Box<ASTNode>,
         operator: String,
         right: Box<ASTNode>,
+        overload: Option<Overload>,
     },
     MethodCall {
         object: Box<ASTNode>,
         method: String,
+        type_arguments: Vec<TypeExpr>,
         arguments: Vec<ASTNode>,
     },
+    IndexExpression {
+        object: Box<ASTNode>,
+        index: Box<ASTNode>,
+    },
+    SliceExpression {
+        object: Box<ASTNode>,
+        start: Option<Box<ASTNode>>,
+        end: Option<Box<ASTNode>>,
+    },
+    Assignment {
+        target: Box<ASTNode>,
+        value: Box<ASTNode>,
+    },
     ExpressionStatement(Box<ASTNode>),
     IfStatement {
         condition: Box<ASTNode>,
@@ -58,7 +186,7 @@ pub enum ASTNode {
     },
     TypeCast {
         expression: Box<ASTNode>,
-        target_type: String,
+        target_type: TypeExpr,
     },
     ConditionalExpression {
         condition: Box<ASTNode>,
@@ -67,20 +195,194 @@ pub enum ASTNode {
     },
     TryCatch {
         try_block: Vec<ASTNode>,
-        catch_variable: Option<String>,
-        catch_block: Vec<ASTNode>,
+        catch_clauses: Vec<CatchClause>,
         finally_block: Option<Vec<ASTNode>>,
     },
+    ThrowStatement(Box<ASTNode>),
     ClassDeclaration {
+        attributes: Vec<Attribute>,
         name: String,
+        type_params: Vec<TypeParameter>,
         extends: Option<String>,
         implements: Vec<String>,
+        constructor: Option<Box<ASTNode>>,
         methods: Vec<ASTNode>,
         properties: Vec<ASTNode>,
     },
+    Constructor {
+        visibility: Visibility,
+        params: Vec<Parameter>,
+        body: Vec<ASTNode>,
+    },
+    FieldDeclaration {
+        attributes: Vec<Attribute>,
+        visibility: Visibility,
+        is_static: bool,
+        var_type: TypeExpr,
+        name: String,
+        value: Option<Box<ASTNode>>,
+    },
+    MethodDeclaration {
+        visibility: Visibility,
+        is_static: bool,
+        function: Box<ASTNode>,
+    },
+    InterfaceDeclaration {
+        name: String,
+        type_params: Vec<TypeParameter>,
+        extends: Vec<String>,
+        methods: Vec<ASTNode>,
+    },
+    EnumDeclaration {
+        name: String,
+        type_params: Vec<TypeParameter>,
+        variants: Vec<EnumVariant>,
+    },
+    RecordDeclaration {
+        name: String,
+        type_params: Vec<TypeParameter>,
+        fields: Vec<(String, TypeExpr)>,
+    },
+    RecordLiteral {
+        type_name: String,
+        fields: Vec<(String, ASTNode)>,
+    },
+    VariantPath {
+        enum_name: String,
+        variant: String,
+    },
+    FunctionSignature {
+        name: String,
+        type_params: Vec<TypeParameter>,
+        params: Vec<Parameter>,
+        return_type: TypeExpr,
+    },
     Block(Vec<ASTNode>),
+    SeqBlock(Vec<ASTNode>),
+    ParBlock(Vec<ASTNode>),
+    AtBlock {
+        offset: Box<ASTNode>,
+        body: Vec<ASTNode>,
+    },
+    NoteEvent {
+        note: Box<ASTNode>,
+        duration: Option<Box<ASTNode>>,
+    },
+    BlockExpression {
+        statements: Vec<ASTNode>,
+        value: Option<Box<ASTNode>>,
+    },
+    IfExpression {
+        condition: Box<ASTNode>,
+        then_branch: Box<ASTNode>,
+        else_branch: Option<Box<ASTNode>>,
+    },
     MatchExpression {
         expression: Box<ASTNode>,
-        cases: Vec<(ASTNode, Vec<ASTNode>)>,
+        cases: Vec<MatchCase>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseImport {
+    pub path: Vec<String>,
+    pub alias: Option<String>,
+    pub items: Vec<ImportItem>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportItem {
+    pub name: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub fields: Vec<TypeExpr>,
+}
+
+// `@name` or `@name(arguments)` in front of a function, class, variable,
+// constant or field.
+#[derive(Debug)]
+pub struct Attribute {
+    pub name: String,
+    pub arguments: Vec<ASTNode>,
+}
+
+// `Type name`, `Type name = default` or a trailing `Type... name`, which
+// collects the remaining positional arguments.
+#[derive(Debug)]
+pub struct Parameter {
+    pub name: String,
+    pub param_type: TypeExpr,
+    pub default: Option<Box<ASTNode>>,
+    pub variadic: bool,
+}
+
+// The class method a binary operator dispatches to, filled in by
+// `semantic::resolve_operators`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overload {
+    pub class_name: String,
+    pub method: String,
+    pub operand_type: TypeExpr,
+}
+
+// `operator +` in a class body declares the method `op_add`, and so on.
+pub const OPERATOR_METHODS: &[(&str, &str)] = &[
+    ("+", "op_add"),
+    ("-", "op_sub"),
+    ("*", "op_mul"),
+    ("/", "op_div"),
+    ("==", "op_eq"),
+    ("!=", "op_ne"),
+    ("<", "op_lt"),
+    (">", "op_gt"),
+    ("<=", "op_le"),
+    (">=", "op_ge"),
+    ("&", "op_and"),
+    ("|", "op_or"),
+];
+
+pub fn operator_method(operator: &str) -> Option<&'static str> {
+    OPERATOR_METHODS.iter()
+        .find(|(symbol, _)| *symbol == operator)
+        .map(|(_, method)| *method)
+}
+
+// `catch (NoteError e) { ... }`; `error_type` is None for a catch-all,
+// written `catch (_)`, `catch (e)` or `catch ()`.
+#[derive(Debug)]
+pub struct CatchClause {
+    pub error_type: Option<TypeExpr>,
+    pub variable: Option<String>,
+    pub body: Vec<ASTNode>,
+}
+
+#[derive(Debug)]
+pub struct MatchCase {
+    pub pattern: Pattern,
+    pub guard: Option<Box<ASTNode>>,
+    pub body: Box<ASTNode>,
+}
+
+#[derive(Debug)]
+pub enum Pattern {
+    Wildcard,
+    Literal(String),
+    Binding(String),
+    Range {
+        start: String,
+        end: String,
+        inclusive: bool,
+    },
+    Array(Vec<Pattern>),
+    Rest(Option<String>),
+    Object(Vec<(String, Pattern)>),
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Vec<Pattern>,
     },
 }
@@ -10,14 +10,25 @@ pub enum TokenType {
     Try,
     Catch,
     Finally,
+    Throw,
     Class,
     Interface,
     Match,
+    Enum,
+    Record,
+    Let,
+    Var,
+    Const,
+    Pub,
+    Priv,
+    Static,
     
     // Identifiers and literals
     Identifier,
     Type,
     Number,
+    Quantity,
+    ChordSymbol,
     String,
     
     // Symbols
@@ -32,9 +43,14 @@ pub enum TokenType {
     RightBrace,    // }
     Comma,         // ,
     Colon,         // :
+    DoubleColon,   // ::
     Asterisk,      // *
     Dot,           // .
+    DotDot,        // ..
+    DotDotEqual,   // ..=
+    Ellipsis,      // ...
     Dollar,        // $
+    At,            // @
     Question,      // ?
     Plus,          // +
     Minus,         // -
@@ -42,6 +58,7 @@ pub enum TokenType {
     Tilde,         // ~
     Ampersand,     // &
     Pipe,          // |
+    PipeGreater,   // |>
     DoubleEqual,   // ==
     NotEqual,      // !=
     LessThan,      // <
@@ -49,6 +66,7 @@ pub enum TokenType {
     LessEqual,     // <=
     GreaterEqual,  // >=
     Arrow,         // =>
+    ThinArrow,     // ->
     
     EOF,
     Unknown,
@@ -61,17 +79,15 @@ pub struct Token {
     pub line: usize,
 }
 
-pub struct Lexer<'a> {
-    source: &'a str,
+pub struct Lexer {
     chars: Vec<char>,
     current: usize,
     line: usize,
 }
 
-impl<'a> Lexer<'a> {
-    pub fn new(source: &'a str) -> Self {
+impl Lexer {
+    pub fn new(source: &str) -> Self {
         Lexer {
-            source,
             chars: source.chars().collect(),
             current: 0,
             line: 1,
@@ -179,26 +195,69 @@ impl<'a> Lexer<'a> {
                 lexeme: String::from(","),
                 line: self.line,
             },
-            ':' => Token {
-                token_type: TokenType::Colon,
-                lexeme: String::from(":"),
-                line: self.line,
+            ':' => {
+                if self.peek() == ':' {
+                    self.advance();
+                    Token {
+                        token_type: TokenType::DoubleColon,
+                        lexeme: String::from("::"),
+                        line: self.line,
+                    }
+                } else {
+                    Token {
+                        token_type: TokenType::Colon,
+                        lexeme: String::from(":"),
+                        line: self.line,
+                    }
+                }
             },
             '*' => Token {
                 token_type: TokenType::Asterisk,
                 lexeme: String::from("*"),
                 line: self.line,
             },
-            '.' => Token {
-                token_type: TokenType::Dot,
-                lexeme: String::from("."),
-                line: self.line,
+            '.' => {
+                if self.peek() == '.' && self.peek_next() == '=' {
+                    self.advance();
+                    self.advance();
+                    Token {
+                        token_type: TokenType::DotDotEqual,
+                        lexeme: String::from("..="),
+                        line: self.line,
+                    }
+                } else if self.peek() == '.' && self.peek_next() == '.' {
+                    self.advance();
+                    self.advance();
+                    Token {
+                        token_type: TokenType::Ellipsis,
+                        lexeme: String::from("..."),
+                        line: self.line,
+                    }
+                } else if self.peek() == '.' {
+                    self.advance();
+                    Token {
+                        token_type: TokenType::DotDot,
+                        lexeme: String::from(".."),
+                        line: self.line,
+                    }
+                } else {
+                    Token {
+                        token_type: TokenType::Dot,
+                        lexeme: String::from("."),
+                        line: self.line,
+                    }
+                }
             },
             '$' => Token {
                 token_type: TokenType::Dollar,
                 lexeme: String::from("$"),
                 line: self.line,
             },
+            '@' => Token {
+                token_type: TokenType::At,
+                lexeme: String::from("@"),
+                line: self.line,
+            },
             '?' => Token {
                 token_type: TokenType::Question,
                 lexeme: String::from("?"),
@@ -209,10 +268,21 @@ impl<'a> Lexer<'a> {
                 lexeme: String::from("+"),
                 line: self.line,
             },
-            '-' => Token {
-                token_type: TokenType::Minus,
-                lexeme: String::from("-"),
-                line: self.line,
+            '-' => {
+                if self.peek() == '>' {
+                    self.advance();
+                    Token {
+                        token_type: TokenType::ThinArrow,
+                        lexeme: String::from("->"),
+                        line: self.line,
+                    }
+                } else {
+                    Token {
+                        token_type: TokenType::Minus,
+                        lexeme: String::from("-"),
+                        line: self.line,
+                    }
+                }
             },
             '~' => Token {
                 token_type: TokenType::Tilde,
@@ -267,21 +337,33 @@ impl<'a> Lexer<'a> {
                     }
                 }
             },
+            '"' => self.string(),
             '&' => Token {
                 token_type: TokenType::Ampersand,
                 lexeme: String::from("&"),
                 line: self.line,
             },
-            '|' => Token {
-                token_type: TokenType::Pipe,
-                lexeme: String::from("|"),
-                line: self.line,
+            '|' => {
+                if self.peek() == '>' {
+                    self.advance();
+                    Token {
+                        token_type: TokenType::PipeGreater,
+                        lexeme: String::from("|>"),
+                        line: self.line,
+                    }
+                } else {
+                    Token {
+                        token_type: TokenType::Pipe,
+                        lexeme: String::from("|"),
+                        line: self.line,
+                    }
+                }
             },
             _ => {
                 if self.is_alpha(c) {
-                    return self.identifier_or_keyword(c);
+                    self.identifier_or_keyword(c)
                 } else if self.is_digit(c) {
-                    return self.number(c);
+                    self.number(c)
                 } else {
                     Token {
                         token_type: TokenType::Unknown,
@@ -300,6 +382,14 @@ impl<'a> Lexer<'a> {
         while !self.is_at_end() && (self.is_alpha(self.peek()) || self.is_digit(self.peek())) {
             identifier.push(self.advance());
         }
+
+        // a sharp belongs to the pitch name: `C#4`, `F#m7`
+        if identifier.len() == 1 && matches!(first_char, 'A'..='G') && self.peek() == '#' {
+            identifier.push(self.advance());
+            while !self.is_at_end() && (self.is_alpha(self.peek()) || self.is_digit(self.peek())) {
+                identifier.push(self.advance());
+            }
+        }
         
         let token_type = match identifier.as_str() {
             "use" => TokenType::Use,
@@ -311,11 +401,22 @@ impl<'a> Lexer<'a> {
             "try" => TokenType::Try,
             "catch" => TokenType::Catch,
             "finally" => TokenType::Finally,
+            "throw" => TokenType::Throw,
             "class" => TokenType::Class,
             "interface" => TokenType::Interface,
             "match" => TokenType::Match,
+            "enum" => TokenType::Enum,
+            "record" => TokenType::Record,
+            "let" => TokenType::Let,
+            "var" => TokenType::Var,
+            "const" => TokenType::Const,
+            "pub" => TokenType::Pub,
+            "priv" => TokenType::Priv,
+            "static" => TokenType::Static,
             _ => {
-                if identifier.chars().next().unwrap().is_uppercase() {
+                if self.is_chord_symbol(&identifier) {
+                    TokenType::ChordSymbol
+                } else if identifier.chars().next().unwrap().is_uppercase() {
                     TokenType::Type
                 } else {
                     TokenType::Identifier
@@ -330,6 +431,64 @@ impl<'a> Lexer<'a> {
         }
     }
     
+    // Root, optional accidental, a quality and any number of extensions:
+    // `Cmaj7`, `Dm7b5`, `F#sus4`, `Bbdom7add13`. Without a quality it stays a
+    // note or type name, so `C4` and `Bb` are not chord symbols.
+    fn is_chord_symbol(&self, identifier: &str) -> bool {
+        if !matches!(identifier.chars().next(), Some('A'..='G')) {
+            return false;
+        }
+        let mut rest = &identifier[1..];
+        if let Some(stripped) = rest.strip_prefix('#').or_else(|| rest.strip_prefix('b')) {
+            rest = stripped;
+        }
+
+        match ["maj", "min", "dim", "aug", "sus", "dom", "m"].iter().find(|quality| rest.starts_with(*quality)) {
+            Some(quality) => rest = &rest[quality.len()..],
+            None => return false,
+        }
+
+        while !rest.is_empty() {
+            let prefix = ["add", "sus", "maj", "b"].iter()
+                .find(|prefix| rest.starts_with(*prefix))
+                .map_or(0, |prefix| prefix.len());
+            let digits = rest[prefix..].chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 {
+                return false;
+            }
+            rest = &rest[prefix + digits..];
+        }
+
+        true
+    }
+
+    fn string(&mut self) -> Token {
+        let start_line = self.line;
+        let mut string = String::from("\"");
+
+        while !self.is_at_end() && self.peek() != '"' {
+            if self.peek() == '\n' {
+                self.line += 1;
+            }
+            string.push(self.advance());
+        }
+
+        if self.is_at_end() {
+            return Token {
+                token_type: TokenType::Unknown,
+                lexeme: string,
+                line: start_line,
+            };
+        }
+        string.push(self.advance()); // closing '"'
+
+        Token {
+            token_type: TokenType::String,
+            lexeme: string,
+            line: start_line,
+        }
+    }
+
     fn number(&mut self, first_digit: char) -> Token {
         let mut number = String::new();
         number.push(first_digit);
@@ -345,6 +504,19 @@ impl<'a> Lexer<'a> {
                 number.push(self.advance());
             }
         }
+
+        // a unit glued onto the number (`120bpm`, `10ms`) makes it a quantity
+        if !self.is_at_end() && self.is_alpha(self.peek()) {
+            while !self.is_at_end() && self.is_alpha(self.peek()) {
+                number.push(self.advance());
+            }
+
+            return Token {
+                token_type: TokenType::Quantity,
+                lexeme: number,
+                line: self.line,
+            };
+        }
         
         Token {
             token_type: TokenType::Number,
@@ -384,7 +556,7 @@ impl<'a> Lexer<'a> {
     }
     
     fn is_digit(&self, c: char) -> bool {
-        c.is_digit(10)
+        c.is_ascii_digit()
     }
     
     fn skip_whitespace(&mut self) {
@@ -1,6 +1,10 @@
+// the package is called `VYL`, which rustc would otherwise flag as a non-snake-case crate name
+#![allow(non_snake_case)]
+
 pub mod lexer;
 pub mod parser;
 pub mod ast;
-// pub mod semantic;
+pub mod semantic;
+pub mod module;
 // pub mod codegen;
 
@@ -1,7 +1,8 @@
-use VYL::lexer::Lexer;
-use VYL::parser::Parser;
+use VYL::ast::ASTNode;
+use VYL::module::ModuleGraph;
+use VYL::semantic;
 use std::env;
-use std::fs;
+use std::path::Path;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -11,35 +12,46 @@ fn main() {
         std::process::exit(1);
     }
 
-    let source_path = &args[1]; 
-    let source_code = match fs::read_to_string(source_path) {
-        Ok(content) => content,
+    let source_path = &args[1];
+ 
+    let mut graph = match ModuleGraph::load(Path::new(source_path)) {
+        Ok(graph) => graph,
         Err(e) => {
-            eprintln!("Error reading file '{}': {}", source_path, e);
+            eprintln!("{}", e);
             std::process::exit(1);
         }
     };
- 
-    let mut lexer = Lexer::new(&source_code); 
-    let tokens = lexer.tokenize();
-    
+
     println!("Tokens:");
-    for token in &tokens {
+    for token in &graph.entry().tokens {
         println!("{:?}", token);
     }
-    
-    let mut parser = Parser::new(tokens);    
-    match parser.parse() {
-        Ok(ast) => {
-            println!("\nAST:");
-            println!("{:#?}", ast);
-            
-            // TODO: Semantic analysis
-            // TODO: Code generation
-        },
-        Err(e) => {
-            eprintln!("Error parsing: {}", e);
-            std::process::exit(1);
+
+    for module in graph.modules_mut() {
+        semantic::resolve_captures(&mut module.ast);
+        if let Err(errors) = semantic::resolve_operators(&mut module.ast) {
+            report(&module.path, errors);
         }
     }
+
+    for &id in graph.load_order() {
+        let module = graph.get(id);
+        let imports: Vec<&ASTNode> = graph.dependencies(id).map(|dependency| &dependency.ast).collect();
+        if let Err(errors) = semantic::check(&module.ast, &imports) {
+            report(&module.path, errors);
+        }
+    }
+
+    for &id in graph.load_order() {
+        let module = graph.get(id);
+        println!("\nAST ({}):", module.name);
+        println!("{:#?}", module.ast);
+    }
+}
+
+fn report(path: &Path, errors: Vec<String>) -> ! {
+    for error in errors {
+        eprintln!("Error in '{}': {}", path.display(), error);
+    }
+    std::process::exit(1);
 }
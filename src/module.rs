@@ -0,0 +1,217 @@
+use crate::ast::ASTNode;
+use crate::lexer::{Lexer, Token};
+use crate::parser::Parser;
+use crate::semantic::walk;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub type ModuleId = usize;
+
+#[derive(Debug)]
+pub struct Module {
+    pub name: String,
+    pub path: PathBuf,
+    pub tokens: Vec<Token>,
+    pub ast: ASTNode,
+    pub imports: Vec<ModuleId>,
+}
+
+#[derive(Debug)]
+pub struct ModuleGraph {
+    modules: Vec<Module>,
+    order: Vec<ModuleId>,
+}
+
+impl ModuleGraph {
+    // Loads `entry` and everything it imports, using VYL_PATH and the
+    // `lib/` directory next to the entry file as extra search locations.
+    pub fn load(entry: &Path) -> Result<ModuleGraph, String> {
+        ModuleLoader::from_env(entry).load(entry)
+    }
+
+    pub fn entry(&self) -> &Module {
+        &self.modules[0]
+    }
+
+    pub fn get(&self, id: ModuleId) -> &Module {
+        &self.modules[id]
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|module| module.name == name)
+    }
+
+    pub fn modules(&self) -> &[Module] {
+        &self.modules
+    }
+
+    pub fn modules_mut(&mut self) -> &mut [Module] {
+        &mut self.modules
+    }
+
+    pub fn dependencies(&self, id: ModuleId) -> impl Iterator<Item = &Module> {
+        self.modules[id].imports.iter().map(|import| &self.modules[*import])
+    }
+
+    // Every module comes after the modules it imports; the entry is last.
+    pub fn load_order(&self) -> &[ModuleId] {
+        &self.order
+    }
+}
+
+pub struct ModuleLoader {
+    search_path: Vec<PathBuf>,
+    project_lib: PathBuf,
+}
+
+impl ModuleLoader {
+    pub fn new(search_path: Vec<PathBuf>, project_lib: PathBuf) -> Self {
+        ModuleLoader { search_path, project_lib }
+    }
+
+    pub fn from_env(entry: &Path) -> Self {
+        let search_path = match env::var_os("VYL_PATH") {
+            Some(paths) => env::split_paths(&paths).collect(),
+            None => Vec::new(),
+        };
+        let project_lib = entry.parent().unwrap_or(Path::new(".")).join("lib");
+
+        ModuleLoader::new(search_path, project_lib)
+    }
+
+    pub fn load(&self, entry: &Path) -> Result<ModuleGraph, String> {
+        let name = entry.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| entry.display().to_string());
+
+        let mut state = LoadState {
+            graph: ModuleGraph {
+                modules: Vec::new(),
+                order: Vec::new(),
+            },
+            by_path: HashMap::new(),
+            stack: Vec::new(),
+        };
+        self.load_module(&mut state, name, entry)?;
+
+        Ok(state.graph)
+    }
+
+    fn load_module(&self, state: &mut LoadState, name: String, path: &Path) -> Result<ModuleId, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Error reading file '{}': {}", path.display(), e))?;
+        let tokens = Lexer::new(&source).tokenize();
+        let ast = Parser::new(tokens.clone()).parse()
+            .map_err(|e| format!("Error parsing '{}': {}", path.display(), e))?;
+        let import_paths = imported_paths(&ast);
+
+        let id = state.graph.modules.len();
+        let key = canonical(path);
+        state.graph.modules.push(Module {
+            name,
+            path: path.to_path_buf(),
+            tokens,
+            ast,
+            imports: Vec::new(),
+        });
+        state.by_path.insert(key, id);
+        state.stack.push(id);
+
+        let directory = path.parent().unwrap_or(Path::new("."));
+        for segments in import_paths {
+            let import_name = segments.join(".");
+            let import_path = self.resolve(directory, &segments).ok_or_else(|| {
+                format!(
+                    "Cannot find module '{}' imported from '{}' (searched {})",
+                    import_name,
+                    path.display(),
+                    self.search_directories(directory)
+                        .iter()
+                        .map(|dir| format!("'{}'", dir.display()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })?;
+
+            let import_id = match state.by_path.get(&canonical(&import_path)) {
+                Some(&existing) if state.stack.contains(&existing) => {
+                    return Err(state.cycle_trace(existing));
+                }
+                Some(&existing) => existing,
+                None => self.load_module(state, import_name, &import_path)?,
+            };
+            if !state.graph.modules[id].imports.contains(&import_id) {
+                state.graph.modules[id].imports.push(import_id);
+            }
+        }
+
+        state.stack.pop();
+        state.graph.order.push(id);
+        Ok(id)
+    }
+
+    // `use [theory.scales]` becomes `theory/scales.vyl` or `theory/scales/mod.vyl`
+    // under the first search directory that has one.
+    fn resolve(&self, importer_directory: &Path, segments: &[String]) -> Option<PathBuf> {
+        let relative: PathBuf = segments.iter().collect();
+
+        self.search_directories(importer_directory).into_iter().find_map(|directory| {
+            let file = directory.join(&relative).with_extension("vyl");
+            let package = directory.join(&relative).join("mod.vyl");
+
+            if file.is_file() {
+                Some(file)
+            } else if package.is_file() {
+                Some(package)
+            } else {
+                None
+            }
+        })
+    }
+
+    fn search_directories(&self, importer_directory: &Path) -> Vec<PathBuf> {
+        let mut directories = vec![importer_directory.to_path_buf()];
+        directories.extend(self.search_path.iter().cloned());
+        directories.push(self.project_lib.clone());
+        directories
+    }
+}
+
+struct LoadState {
+    graph: ModuleGraph,
+    by_path: HashMap<PathBuf, ModuleId>,
+    stack: Vec<ModuleId>,
+}
+
+impl LoadState {
+    fn cycle_trace(&self, repeated: ModuleId) -> String {
+        let start = self.stack.iter().position(|id| *id == repeated).unwrap_or(0);
+        let mut trace = String::from("Import cycle detected:");
+        for (step, id) in self.stack[start..].iter().chain(std::iter::once(&repeated)).enumerate() {
+            let module = &self.graph.modules[*id];
+            let arrow = if step == 0 { "   " } else { "-> " };
+            trace.push_str(&format!("\n  {}{} ({})", arrow, module.name, module.path.display()));
+        }
+        trace
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn imported_paths(ast: &ASTNode) -> Vec<Vec<String>> {
+    let mut paths: Vec<Vec<String>> = Vec::new();
+    walk(ast, &mut |node| {
+        if let ASTNode::UseStatement { imports, .. } = node {
+            for import in imports {
+                if !paths.contains(&import.path) {
+                    paths.push(import.path.clone());
+                }
+            }
+        }
+    });
+    paths
+}
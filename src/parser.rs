@@ -1,6 +1,70 @@
-use crate::ast::ASTNode;
+use crate::ast::{operator_method, ASTNode, Attribute, CatchClause, EnumVariant, ImportItem, MatchCase, Parameter, Pattern, TypeExpr, TypeParameter, UseImport, Visibility};
 use crate::lexer::{Token, TokenType};
 
+#[derive(Clone, Copy)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy)]
+enum Infix {
+    Binary(&'static str),
+    Assignment,
+    Conditional,
+    Pipeline,
+    Call,
+    Member,
+    Index,
+}
+
+struct Operator {
+    token: TokenType,
+    precedence: u8,
+    associativity: Associativity,
+    infix: Infix,
+}
+
+const fn left(token: TokenType, precedence: u8, infix: Infix) -> Operator {
+    Operator { token, precedence, associativity: Associativity::Left, infix }
+}
+
+const fn right(token: TokenType, precedence: u8, infix: Infix) -> Operator {
+    Operator { token, precedence, associativity: Associativity::Right, infix }
+}
+
+const PREFIX_PRECEDENCE: u8 = 10;
+const POSTFIX_PRECEDENCE: u8 = 11;
+
+// Infix and postfix operators, loosest first. A new binary operator only
+// needs a row here (and its token in the lexer).
+const OPERATORS: &[Operator] = &[
+    right(TokenType::Equal, 1, Infix::Assignment),
+    right(TokenType::Question, 2, Infix::Conditional),
+    left(TokenType::PipeGreater, 3, Infix::Pipeline),
+    left(TokenType::Pipe, 4, Infix::Binary("|")),
+    left(TokenType::Ampersand, 5, Infix::Binary("&")),
+    left(TokenType::DoubleEqual, 6, Infix::Binary("==")),
+    left(TokenType::NotEqual, 6, Infix::Binary("!=")),
+    left(TokenType::LessThan, 7, Infix::Binary("<")),
+    left(TokenType::GreaterThan, 7, Infix::Binary(">")),
+    left(TokenType::LessEqual, 7, Infix::Binary("<=")),
+    left(TokenType::GreaterEqual, 7, Infix::Binary(">=")),
+    left(TokenType::Plus, 8, Infix::Binary("+")),
+    left(TokenType::Minus, 8, Infix::Binary("-")),
+    left(TokenType::Asterisk, 9, Infix::Binary("*")),
+    left(TokenType::Slash, 9, Infix::Binary("/")),
+    left(TokenType::LeftParen, POSTFIX_PRECEDENCE, Infix::Call),
+    left(TokenType::Dot, POSTFIX_PRECEDENCE, Infix::Member),
+    left(TokenType::LeftBracket, POSTFIX_PRECEDENCE, Infix::Index),
+];
+
+const PREFIX_OPERATORS: &[(TokenType, &str)] = &[
+    (TokenType::Minus, "-"),
+    (TokenType::Bang, "!"),
+    (TokenType::Tilde, "~"),
+];
+
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
@@ -25,8 +89,19 @@ impl Parser {
     }
     
     fn parse_statement(&mut self) -> Result<ASTNode, String> {
-        if self.match_token(TokenType::Use) {
-            return self.parse_use_statement();
+        if self.check(TokenType::At) {
+            let attributes = self.parse_attributes()?;
+            let mut statement = self.parse_statement()?;
+            attach_attributes(&mut statement, attributes)?;
+            return Ok(statement);
+        } else if self.match_token(TokenType::Use) {
+            return self.parse_use_statement(Visibility::Private);
+        } else if self.match_token(TokenType::Pub) {
+            if self.match_token(TokenType::Use) {
+                return self.parse_use_statement(Visibility::Public);
+            }
+            self.consume(TokenType::Const, "Expected 'use' or 'const' after 'pub'")?;
+            return self.parse_const_declaration(Visibility::Public);
         } else if self.match_token(TokenType::Return) {
             return self.parse_return_statement();
         } else if self.match_token(TokenType::If) {
@@ -37,23 +112,43 @@ impl Parser {
             return self.parse_for_loop();
         } else if self.match_token(TokenType::Try) {
             return self.parse_try_catch();
+        } else if self.match_token(TokenType::Throw) {
+            let error = self.parse_expression()?;
+            self.consume(TokenType::Semicolon, "Expected ';' after thrown value")?;
+            return Ok(ASTNode::ThrowStatement(error));
         } else if self.match_token(TokenType::Class) {
             return self.parse_class_declaration();
-        } else if self.match_token(TokenType::Match) {
-            return Ok(ASTNode::ExpressionStatement(Box::new(self.parse_match_expression()?)));
-        /*} else if self.match_token(TokenType::Interface) {
-            return parse_interface_declaration();*/
+        } else if self.check(TokenType::Match) {
+            // the `;` after a bare `match { ... }` is optional, like after a block
+            let expr = self.parse_expression()?;
+            if !self.match_token(TokenType::Semicolon) && !matches!(*expr, ASTNode::MatchExpression { .. }) {
+                return Err(format!("Expected ';' after expression - got {:?} instead", self.peek().token_type));
+            }
+            return Ok(ASTNode::ExpressionStatement(expr));
+        } else if self.match_token(TokenType::Interface) {
+            return self.parse_interface_declaration();
+        } else if self.match_token(TokenType::Enum) {
+            return self.parse_enum_declaration();
+        } else if self.match_token(TokenType::Record) {
+            return self.parse_record_declaration();
+        } else if self.match_token(TokenType::Let) || self.match_token(TokenType::Var) {
+            let mutable = self.previous().token_type == TokenType::Var;
+            return self.parse_variable_declaration(mutable);
+        } else if self.match_token(TokenType::Const) {
+            return self.parse_const_declaration(Visibility::Private);
+        } else if self.is_type_alias() {
+            return self.parse_type_alias();
+        } else if self.is_musical_context() {
+            return self.parse_musical_context();
+        } else if self.is_scheduling_statement() {
+            return self.parse_scheduling_statement();
         } else if self.match_token(TokenType::LeftBrace) {
             return self.parse_block();
-        } else if self.check(TokenType::Type) {
-            return self.parse_variable_declaration();
-        } else if self.check(TokenType::Identifier) {
-            let current_position = self.current;
-            if self.peek_next().token_type == TokenType::Slash {
-                return self.parse_variable_declaration();
+        } else if self.check(TokenType::Type) || self.check(TokenType::Identifier) ||
+            self.check(TokenType::LeftBracket) || self.check(TokenType::LeftParen) {
+            if self.is_variable_declaration() {
+                return self.parse_variable_declaration(true);
             }
-
-            self.current = current_position;
             return self.parse_expression_statement();
         } else if self.match_token(TokenType::Slash) {
             return self.parse_function_declaration();
@@ -62,42 +157,264 @@ impl Parser {
         Err(format!("Unexpected token: {:?}", self.peek()))
     }
     
-    fn parse_use_statement(&mut self) -> Result<ASTNode, String> {
+    fn parse_attributes(&mut self) -> Result<Vec<Attribute>, String> {
+        let mut attributes: Vec<Attribute> = Vec::new();
+
+        while self.match_token(TokenType::At) {
+            let name = self.consume_type_name("Expected attribute name after '@'")?.lexeme;
+            if attributes.iter().any(|attribute| attribute.name == name) {
+                return Err(format!("Duplicate attribute '@{}'", name));
+            }
+
+            let arguments = if self.match_token(TokenType::LeftParen) {
+                self.parse_arguments("Expected ')' after attribute arguments")?
+            } else {
+                Vec::new()
+            };
+            attributes.push(Attribute {
+                name,
+                arguments,
+            });
+        }
+
+        Ok(attributes)
+    }
+
+    fn parse_use_statement(&mut self, visibility: Visibility) -> Result<ASTNode, String> {
         self.consume(TokenType::LeftBracket, "Expected '[' after 'use'")?;
         
-        let package = self.consume(TokenType::Identifier, "Expected package name")?;
+        let mut imports = Vec::new();
+        loop {
+            imports.push(self.parse_use_import()?);
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
         
         self.consume(TokenType::RightBracket, "Expected ']' after package name")?;
         self.consume(TokenType::Semicolon, "Expected ';' after use statement")?;
         
-        Ok(ASTNode::UseStatement(package.lexeme))
+        Ok(ASTNode::UseStatement {
+            visibility,
+            imports,
+        })
+    }
+
+    fn parse_use_import(&mut self) -> Result<UseImport, String> {
+        let mut path = vec![self.consume_type_name("Expected package name")?.lexeme];
+        let mut items = Vec::new();
+
+        while self.match_token(TokenType::Dot) {
+            if self.match_token(TokenType::LeftBrace) {
+                loop {
+                    let name = self.consume_type_name("Expected imported name")?.lexeme;
+                    let alias = self.parse_import_alias()?;
+                    items.push(ImportItem {
+                        name,
+                        alias,
+                    });
+
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightBrace, "Expected '}' after import list")?;
+                break;
+            }
+            path.push(self.consume_type_name("Expected module name after '.'")?.lexeme);
+        }
+
+        let alias = if items.is_empty() {
+            self.parse_import_alias()?
+        } else {
+            None
+        };
+
+        Ok(UseImport {
+            path,
+            alias,
+            items,
+        })
+    }
+
+    fn parse_import_alias(&mut self) -> Result<Option<String>, String> {
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "as" {
+            self.advance();
+            return Ok(Some(self.consume_type_name("Expected alias after 'as'")?.lexeme));
+        }
+        Ok(None)
+    }
+
+    fn is_musical_context(&self) -> bool {
+        if !self.check(TokenType::Identifier) {
+            return false;
+        }
+
+        let next = &self.peek_next().token_type;
+        match self.peek().lexeme.as_str() {
+            "tempo" => is_tempo_start(next),
+            "meter" => *next == TokenType::Number,
+            "key" => *next == TokenType::Type,
+            "with" => *next == TokenType::Identifier &&
+                matches!(self.peek_next().lexeme.as_str(), "tempo" | "meter" | "key"),
+            _ => false,
+        }
+    }
+
+    fn parse_musical_context(&mut self) -> Result<ASTNode, String> {
+        if self.peek().lexeme == "with" {
+            self.advance();
+
+            let mut settings = Vec::new();
+            loop {
+                settings.push(self.parse_context_setting()?);
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+
+            self.consume(TokenType::LeftBrace, "Expected '{' after 'with' settings")?;
+            let mut body = Vec::new();
+            while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                body.push(self.parse_statement()?);
+            }
+            self.consume(TokenType::RightBrace, "Expected '}' after 'with' block")?;
+
+            return Ok(ASTNode::WithContext {
+                settings,
+                body,
+            });
+        }
+
+        let setting = self.parse_context_setting()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after musical context declaration")?;
+        Ok(setting)
+    }
+
+    // `wait 1/4;`, `every 1bar { ... }` and `spawn { ... }`. Followed by
+    // anything else these are ordinary names, so `wait(x)` is still a call.
+    fn is_scheduling_statement(&self) -> bool {
+        if !self.check(TokenType::Identifier) {
+            return false;
+        }
+
+        let next = &self.peek_next().token_type;
+        let is_duration = matches!(next, TokenType::Number | TokenType::Quantity | TokenType::Identifier | TokenType::Type);
+        match self.peek().lexeme.as_str() {
+            "wait" | "every" => is_duration,
+            "spawn" => *next == TokenType::LeftBrace,
+            _ => false,
+        }
+    }
+
+    fn parse_scheduling_statement(&mut self) -> Result<ASTNode, String> {
+        match self.peek().lexeme.as_str() {
+            "wait" => {
+                self.advance();
+                let duration = self.parse_expression()?;
+                self.consume(TokenType::Semicolon, "Expected ';' after wait duration")?;
+
+                Ok(ASTNode::WaitStatement(duration))
+            }
+            "every" => {
+                self.advance();
+                let interval = self.parse_expression()?;
+                self.consume(TokenType::LeftBrace, "Expected '{' after 'every' interval")?;
+                let mut body = Vec::new();
+                while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+                    body.push(self.parse_statement()?);
+                }
+                self.consume(TokenType::RightBrace, "Expected '}' after 'every' block")?;
+
+                Ok(ASTNode::EveryLoop {
+                    interval,
+                    body,
+                })
+            }
+            _ => {
+                let spawn = self.parse_spawn()?;
+                self.match_token(TokenType::Semicolon);
+
+                Ok(ASTNode::ExpressionStatement(spawn))
+            }
+        }
+    }
+
+    // `spawn { ... }` starts a task running alongside the current one and
+    // evaluates to its handle.
+    fn parse_spawn(&mut self) -> Result<Box<ASTNode>, String> {
+        self.advance();
+        self.consume(TokenType::LeftBrace, "Expected '{' after 'spawn'")?;
+        let mut body = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            body.push(self.parse_statement()?);
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after 'spawn' block")?;
+
+        Ok(Box::new(ASTNode::Spawn(body)))
+    }
+
+    fn parse_context_setting(&mut self) -> Result<ASTNode, String> {
+        let keyword = self.consume(TokenType::Identifier, "Expected 'tempo', 'meter' or 'key'")?;
+
+        match keyword.lexeme.as_str() {
+            "tempo" => {
+                if !is_tempo_start(&self.peek().token_type) {
+                    return Err(format!("Expected tempo after 'tempo' - got {:?} instead", self.peek().token_type));
+                }
+                Ok(ASTNode::TempoDeclaration(self.parse_expression()?))
+            }
+            "meter" => {
+                let beats = self.consume(TokenType::Number, "Expected beats per bar after 'meter'")?;
+                self.consume(TokenType::Slash, "Expected '/' in meter")?;
+                let beat_unit = self.consume(TokenType::Number, "Expected beat unit after '/'")?;
+
+                Ok(ASTNode::MeterDeclaration {
+                    beats: beats.lexeme,
+                    beat_unit: beat_unit.lexeme,
+                })
+            }
+            "key" => {
+                let tonic = self.consume(TokenType::Type, "Expected tonic after 'key'")?.lexeme;
+                let mode = if self.check(TokenType::Identifier) {
+                    self.advance().lexeme
+                } else {
+                    String::from("major")
+                };
+
+                Ok(ASTNode::KeyDeclaration {
+                    tonic,
+                    mode,
+                })
+            }
+            other => Err(format!("Expected 'tempo', 'meter' or 'key', got '{}'", other)),
+        }
     }
 
     fn parse_return_statement(&mut self) -> Result<ASTNode, String> {
-        let value = self.parse_expression()?;
+        let value = if self.check(TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
         self.consume(TokenType::Semicolon, "Expected ';' after return statement")?;
     
         Ok(ASTNode::ReturnStatement(value))
     }
     
-    fn parse_variable_declaration(&mut self) -> Result<ASTNode, String> {
-        println!("Current token: {:?}", self.peek());
-
-        let var_type;
-        if self.check(TokenType::Type) {
-            var_type = self.advance().lexeme;
-        } else if self.check(TokenType::Identifier) {
+    fn parse_variable_declaration(&mut self, mutable: bool) -> Result<ASTNode, String> {
+        let var_type = if self.check(TokenType::Identifier) {
             // treating it as a type anyway cause im lazy rm
             // see it would've been easier to fix it to "rn" instead of writing this whole comment
             // but oh welli guess this is the state of my world at present. How are you? I hope
             // your day is going well. :)
-            var_type = self.advance().lexeme;
+            TypeExpr::Named(self.advance().lexeme)
         } else {
-            return Err(format!("Expected type or identifier, got {:?}", self.peek().token_type));
-        }
+            self.parse_type()?
+        };
 
-        let type_token = self.peek().clone();
-        println!("Found type: {}, token type: {:?}", var_type, type_token.token_type);
         self.consume(TokenType::Slash, "Expected '/' after type name")?;
         let name = self.consume(TokenType::Identifier, "Expected variable name")?;
         self.consume(TokenType::Slash, "Expected '/' after variable name")?;
@@ -105,12 +422,53 @@ impl Parser {
         let value = self.parse_expression()?;
         self.consume(TokenType::Semicolon, "Expected ';' after variable declaration")?;
         Ok(ASTNode::VariableDeclaration {
+            attributes: Vec::new(),
+            mutable,
+            var_type,
+            name: name.lexeme,
+            value,
+        })
+    }
+
+    fn parse_const_declaration(&mut self, visibility: Visibility) -> Result<ASTNode, String> {
+        let var_type = self.parse_type()?;
+        self.consume(TokenType::Slash, "Expected '/' after type name")?;
+        let name = self.consume_type_name("Expected constant name")?;
+        self.consume(TokenType::Slash, "Expected '/' after constant name")?;
+        self.consume(TokenType::Equal, "Expected '=' after constant declaration")?;
+        let value = self.parse_expression()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after constant declaration")?;
+
+        Ok(ASTNode::ConstDeclaration {
+            attributes: Vec::new(),
+            visibility,
             var_type,
             name: name.lexeme,
             value,
         })
     }
 
+    fn is_type_alias(&self) -> bool {
+        self.check(TokenType::Identifier) &&
+            self.peek().lexeme == "type" &&
+            self.peek_next().token_type == TokenType::Type
+    }
+
+    fn parse_type_alias(&mut self) -> Result<ASTNode, String> {
+        self.advance();
+        let name = self.consume(TokenType::Type, "Expected type alias name")?;
+        let type_params = self.parse_type_parameters()?;
+        self.consume(TokenType::Equal, "Expected '=' after type alias name")?;
+        let target = self.parse_type()?;
+        self.consume(TokenType::Semicolon, "Expected ';' after type alias")?;
+
+        Ok(ASTNode::TypeAlias {
+            name: name.lexeme,
+            type_params,
+            target,
+        })
+    }
+
     /*fn parse_variable_declaration(&mut self) -> Result<ASTNode, String> {
         if !self.check(TokenType::Type) && !self.check(TokenType::Identifier) {
             return Err(format!("Expected type name or identifier, got {:?}", self.peek().token_type));
@@ -144,161 +502,285 @@ impl Parser {
     // not sure if I like this yet... but one thing's for sure: I hate myself for trying :p
     // also, good luck reading this, hotshot!
     fn parse_expression(&mut self) -> Result<Box<ASTNode>, String> {
-        let expr = self.parse_logical_or()?;
+        self.parse_precedence(0)
+    }
 
-        if self.match_token(TokenType::Question) {
-            let then_expr = self.parse_expression()?;
-            self.consume(TokenType::Colon, "Expected ':' in ternary expression")?;
-            let else_expr = self.parse_expression()?;
+    // Pratt loop: parse a prefix expression, then keep folding in operators
+    // from OPERATORS that bind at least as tightly as `min_precedence`.
+    fn parse_precedence(&mut self, min_precedence: u8) -> Result<Box<ASTNode>, String> {
+        let mut expr = self.parse_prefix()?;
 
-            return Ok(Box::new(ASTNode::ConditionalExpression {
-                condition: expr,
-                then_expr,
-                else_expr,
-            }));
-        }
-        Ok(expr)
-    }
+        loop {
+            // `f<Note>(x)` is a call with type arguments, not a comparison
+            if self.check(TokenType::LessThan) &&
+                let Some(type_arguments) = self.parse_call_type_arguments() {
+                self.consume(TokenType::LeftParen, "Expected '(' after type arguments")?;
+                let arguments = self.parse_arguments("Expected ')' after function arguments")?;
+
+                expr = Box::new(ASTNode::FunctionCall {
+                    callee: expr,
+                    type_arguments,
+                    arguments,
+                });
+                continue;
+            }
 
-    fn parse_logical_or(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_logical_and()?;
+            let Some(operator) = OPERATORS.iter().find(|operator| self.check(operator.token.clone())) else {
+                break;
+            };
+            if operator.precedence < min_precedence {
+                break;
+            }
+            self.advance();
 
-        while self.match_token(TokenType::Pipe) {
-            let operator = String::from("|");
-            let right = self.parse_logical_and()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
+            let right_precedence = match operator.associativity {
+                Associativity::Left => operator.precedence + 1,
+                Associativity::Right => operator.precedence,
+            };
+            expr = self.parse_infix(expr, operator.infix, right_precedence)?;
         }
         Ok(expr)
     }
 
-    fn parse_logical_and(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_equality()?;
+    fn parse_prefix(&mut self) -> Result<Box<ASTNode>, String> {
+        if let Some((_, operator)) = PREFIX_OPERATORS.iter().find(|(token, _)| self.check(token.clone())) {
+            self.advance();
+            let operand = self.parse_precedence(PREFIX_PRECEDENCE)?;
+            return Ok(Box::new(ASTNode::UnaryExpression {
+                operator: String::from(*operator),
+                operand,
+            }));
+        }
+
+        if self.check(TokenType::Identifier) &&
+            self.peek().lexeme == "await" &&
+            matches!(self.peek_next().token_type, TokenType::Identifier | TokenType::Type) {
+            self.advance();
+            let operand = self.parse_precedence(PREFIX_PRECEDENCE)?;
+            return Ok(Box::new(ASTNode::Await(operand)));
+        }
 
-        while self.match_token(TokenType::Ampersand) {
-            let operator = String::from("&");
-            let right  = self.parse_equality()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
+        if self.check(TokenType::Type) {
+            let start = self.current;
+            let is_cast = self.parse_type().is_ok() &&
+                self.check(TokenType::Slash) &&
+                self.peek_next().token_type == TokenType::LeftParen;
+            self.current = start;
+
+            if is_cast {
+                return Ok(Box::new(self.parse_type_cast()?));
+            }
         }
-        Ok(expr)
+
+        self.parse_primary()
     }
 
-    fn parse_equality(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_comparison()?;
+    // The operator token has already been consumed; `right_precedence` is
+    // what its right-hand operand must bind at.
+    fn parse_infix(&mut self, left: Box<ASTNode>, infix: Infix, right_precedence: u8) -> Result<Box<ASTNode>, String> {
+        match infix {
+            Infix::Binary(operator) => {
+                let right = self.parse_precedence(right_precedence)?;
+                Ok(Box::new(ASTNode::BinaryExpression {
+                    left,
+                    operator: String::from(operator),
+                    right,
+                    overload: None,
+                }))
+            }
+            Infix::Assignment => {
+                let value = self.parse_precedence(right_precedence)?;
+
+                match *left {
+                    ASTNode::Identifier(_) |
+                    ASTNode::PropertyAccess { .. } |
+                    ASTNode::IndexExpression { .. } => Ok(Box::new(ASTNode::Assignment {
+                        target: left,
+                        value,
+                    })),
+                    _ => Err(format!("Invalid assignment target: {:?}", left)),
+                }
+            }
+            Infix::Conditional => {
+                let then_expr = self.parse_expression()?;
+                self.consume(TokenType::Colon, "Expected ':' in ternary expression")?;
+                let else_expr = self.parse_expression()?;
+
+                Ok(Box::new(ASTNode::ConditionalExpression {
+                    condition: left,
+                    then_expr,
+                    else_expr,
+                }))
+            }
+            // `x |> f(a)` becomes `f(x, a)` and `x |> obj.m(a)` becomes `obj.m(x, a)`.
+            Infix::Pipeline => {
+                let stage = self.parse_precedence(right_precedence)?;
+                match *stage {
+                    ASTNode::FunctionCall { callee, type_arguments, mut arguments } => {
+                        arguments.insert(0, *left);
+                        Ok(Box::new(ASTNode::FunctionCall {
+                            callee,
+                            type_arguments,
+                            arguments,
+                        }))
+                    }
+                    ASTNode::MethodCall { object, method, type_arguments, mut arguments } => {
+                        arguments.insert(0, *left);
+                        Ok(Box::new(ASTNode::MethodCall {
+                            object,
+                            method,
+                            type_arguments,
+                            arguments,
+                        }))
+                    }
+                    other => Err(format!("Expected a call after '|>', got {:?}", other)),
+                }
+            }
+            Infix::Call => {
+                let arguments = self.parse_arguments("Expected ')' after function arguments")?;
+                Ok(Box::new(ASTNode::FunctionCall {
+                    callee: left,
+                    type_arguments: Vec::new(),
+                    arguments,
+                }))
+            }
+            Infix::Member => {
+                let property = self.consume(TokenType::Identifier, "Expected property name after '.'")?;
+                let type_arguments = self.parse_call_type_arguments().unwrap_or_default();
 
-        while self.match_token(TokenType::DoubleEqual) || self.match_token(TokenType::NotEqual) {
-            let operator = if self.previous().token_type == TokenType::DoubleEqual {
-                String::from("==")
-            } else {
-                String::from("!=")
-            };
-            let right = self.parse_comparison()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
+                if self.match_token(TokenType::LeftParen) {
+                    let arguments = self.parse_arguments("Expected ')' after method arguments")?;
+
+                    Ok(Box::new(ASTNode::MethodCall {
+                        object: left,
+                        method: property.lexeme,
+                        type_arguments,
+                        arguments,
+                    }))
+                } else {
+                    Ok(Box::new(ASTNode::PropertyAccess{
+                        object: left,
+                        property: property.lexeme,
+                    }))
+                }
+            }
+            Infix::Index => self.parse_index(left),
         }
-        Ok(expr)
     }
 
-    fn parse_comparison(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_term()?;
+    // `f<Note>(x)` and `a < b > (c)` look the same, so explicit type arguments
+    // only count when the whole `<...>` parses as types and a '(' follows.
+    // Returns None (and rewinds) when this isn't a call at all.
+    fn parse_call_type_arguments(&mut self) -> Option<Vec<TypeExpr>> {
+        if self.check(TokenType::LeftParen) {
+            return Some(Vec::new());
+        }
+        if !self.check(TokenType::LessThan) {
+            return None;
+        }
 
-        while self.match_token(TokenType::LessThan) ||
-              self.match_token(TokenType::GreaterThan) ||
-              self.match_token(TokenType::LessEqual) ||
-              self.match_token(TokenType::GreaterEqual) {
-            let operator = match self.previous().token_type {
-                TokenType::LessThan => String::from("<"),
-                TokenType::GreaterThan => String::from(">"),
-                TokenType::LessEqual => String::from("<="),
-                TokenType::GreaterEqual => String::from(">="),
-                _ => unreachable!(),
-            };
-            let right = self.parse_term()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
+        let start = self.current;
+        self.advance();
+        let mut type_arguments = Vec::new();
+        loop {
+            match self.parse_type() {
+                Ok(type_argument) => type_arguments.push(type_argument),
+                Err(_) => {
+                    self.current = start;
+                    return None;
+                }
+            }
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+
+        if self.match_token(TokenType::GreaterThan) && self.check(TokenType::LeftParen) {
+            Some(type_arguments)
+        } else {
+            self.current = start;
+            None
         }
-        Ok(expr)
     }
 
-    fn parse_term(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_factor()?;
+    // Positional arguments first, then any `name: value` pairs.
+    fn parse_arguments(&mut self, error_message: &str) -> Result<Vec<ASTNode>, String> {
+        let mut arguments = Vec::new();
 
-        while self.match_token(TokenType::Plus) || self.match_token(TokenType::Minus) {
-            let operator = if self.previous().token_type == TokenType::Plus {
-                String::from("+")
-            } else {
-                String::from("-")
-            };
-            let right = self.parse_factor()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
+        if !self.check(TokenType::RightParen) {
+            loop {
+                if self.check(TokenType::Identifier) && self.peek_next().token_type == TokenType::Colon {
+                    let name = self.advance().lexeme;
+                    self.advance();
+                    if arguments.iter().any(|argument| matches!(argument, ASTNode::NamedArgument { name: earlier, .. } if *earlier == name)) {
+                        return Err(format!("Argument '{}' is given more than once", name));
+                    }
+
+                    arguments.push(ASTNode::NamedArgument {
+                        name,
+                        value: self.parse_expression()?,
+                    });
+                } else {
+                    if let Some(ASTNode::NamedArgument { name, .. }) = arguments.last() {
+                        return Err(format!("Positional argument after named argument '{}'", name));
+                    }
+                    arguments.push(*self.parse_expression()?);
+                }
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
         }
-        Ok(expr)
+        self.consume(TokenType::RightParen, error_message)?;
+
+        Ok(arguments)
     }
 
-    fn parse_factor(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_unary()?;
+    fn parse_index(&mut self, object: Box<ASTNode>) -> Result<Box<ASTNode>, String> {
+        let start = if self.check(TokenType::DotDot) {
+            None
+        } else {
+            Some(self.parse_expression()?)
+        };
 
-        while self.match_token(TokenType::Asterisk) || self.match_token(TokenType::Slash) {
-            let operator = if self.previous().token_type == TokenType::Asterisk {
-                String::from("*")
+        if self.match_token(TokenType::DotDot) {
+            let end = if self.check(TokenType::RightBracket) {
+                None
             } else {
-                String::from("/")
+                Some(self.parse_expression()?)
             };
-            let right = self.parse_unary()?;
-            expr = Box::new(ASTNode::BinaryExpression {
-                left: expr,
-                operator,
-                right,
-            });
-        }
-        Ok(expr)
-    }
+            self.consume(TokenType::RightBracket, "Expected ']' after slice range")?;
 
-    fn parse_unary(&mut self) -> Result<Box<ASTNode>, String> {
-        if self.match_token(TokenType::Minus) || 
-           self.match_token(TokenType::Bang) ||
-           self.match_token(TokenType::Tilde) {
-            let operator = match self.previous().token_type {
-                TokenType::Minus => String::from("-"),
-                TokenType::Bang => String::from("!"),
-                TokenType::Tilde => String::from("~"),
-                _ => unreachable!(),
-            };
-            let operand = self.parse_unary()?;
-            return Ok(Box::new(ASTNode::UnaryExpression {
-                operator,
-                operand,
+            return Ok(Box::new(ASTNode::SliceExpression {
+                object,
+                start,
+                end,
             }));
         }
 
-        if self.check(TokenType::Type) && self.peek_next().token_type == TokenType::Slash {
-            return Ok(Box::new(self.parse_type_cast()?));
-        }
+        let index = start.ok_or_else(|| format!("Expected index expression, got {:?}", self.peek()))?;
+        self.consume(TokenType::RightBracket, "Expected ']' after index")?;
 
-        self.parse_call()
+        Ok(Box::new(ASTNode::IndexExpression {
+            object,
+            index,
+        }))
     }
 
-    fn parse_call(&mut self) -> Result<Box<ASTNode>, String> {
+    /*fn parse_expression(&mut self) -> Result<Box<ASTNode>, String> {
         let mut expr = self.parse_primary()?;
+
         loop {
-            if self.match_token(TokenType::Dot) {
+            if self.match_token(TokenType::Asterisk) {
+                let right = self.parse_primary()?;
+                expr = Box::new(ASTNode::BinaryExpression {
+                    left: expr,
+                    operator: String::from("*"),
+                    right,
+                });
+            } else if self.match_token(TokenType::Dot) {
                 let property = self.consume(TokenType::Identifier, "Expected property name after '.'")?;
 
                 if self.match_token(TokenType::LeftParen) {
@@ -313,6 +795,7 @@ impl Parser {
                             }
                         }
                     }
+
                     self.consume(TokenType::RightParen, "Expected ')' after method arguments")?;
 
                     expr = Box::new(ASTNode::MethodCall {
@@ -321,7 +804,7 @@ impl Parser {
                         arguments,
                     });
                 } else {
-                    expr = Box::new(ASTNode::PropertyAccess{
+                    expr = Box::new(ASTNode::PropertyAccess {
                         object: Box::new(*expr),
                         property: property.lexeme,
                     });
@@ -330,95 +813,132 @@ impl Parser {
                 break;
             }
         }
-        Ok(expr)
-    }
 
-    /*fn parse_expression(&mut self) -> Result<Box<ASTNode>, String> {
-        let mut expr = self.parse_primary()?;
-
-        loop {
-            if self.match_token(TokenType::Asterisk) {
-                let right = self.parse_primary()?;
-                expr = Box::new(ASTNode::BinaryExpression {
-                    left: expr,
-                    operator: String::from("*"),
-                    right,
-                });
-            } else if self.match_token(TokenType::Dot) {
-                let property = self.consume(TokenType::Identifier, "Expected property name after '.'")?;
-
-                if self.match_token(TokenType::LeftParen) {
-                    let mut arguments = Vec::new();
-
-                    if !self.check(TokenType::RightParen) {
-                        loop {
-                            arguments.push(*self.parse_expression()?);
-
-                            if !self.match_token(TokenType::Comma) {
-                                break;
-                            }
-                        }
-                    }
-
-                    self.consume(TokenType::RightParen, "Expected ')' after method arguments")?;
-
-                    expr = Box::new(ASTNode::MethodCall {
-                        object: Box::new(*expr),
-                        method: property.lexeme,
-                        arguments,
-                    });
-                } else {
-                    expr = Box::new(ASTNode::PropertyAccess {
-                        object: Box::new(*expr),
-                        property: property.lexeme,
-                    });
-                }
-            } else {
-                break;
-            }
-        }
-
-        Ok(expr)
-    }*/
+        Ok(expr)
+    }*/
 
     fn parse_primary(&mut self) -> Result<Box<ASTNode>, String> {
         if self.match_token(TokenType::LeftBracket) {
-            return self.parse_array();
-        } else if self.match_token(TokenType::LeftBrace) {
-            return self.parse_object();
-        } else if self.check(TokenType::Number) {
-            let value = self.advance().lexeme;
-            return Ok(Box::new(ASTNode::Literal(value)));
-        } else if self.check(TokenType::String) {
+            self.parse_array()
+        } else if self.check(TokenType::LeftBrace) {
+            let is_object = self.is_object_start();
+            self.advance();
+            if is_object {
+                self.parse_object()
+            } else {
+                self.parse_block_expression()
+            }
+        } else if self.is_composition_start() {
+            self.parse_composition()
+        } else if self.check(TokenType::Identifier) && self.peek().lexeme == "spawn" && self.peek_next().token_type == TokenType::LeftBrace {
+            self.parse_spawn()
+        } else if self.match_token(TokenType::If) {
+            self.parse_if_expression()
+        } else if self.match_token(TokenType::Match) {
+            Ok(Box::new(self.parse_match_expression()?))
+        } else if self.match_token(TokenType::LessThan) {
+            self.parse_chord()
+        } else if self.check(TokenType::ChordSymbol) {
+            let lexeme = self.advance().lexeme;
+            let root_len = if matches!(lexeme[1..].chars().next(), Some('#') | Some('b')) { 2 } else { 1 };
+            Ok(Box::new(ASTNode::ChordSymbol {
+                root: lexeme[..root_len].to_string(),
+                quality: lexeme[root_len..].to_string(),
+            }))
+        } else if self.is_scale_literal() {
+            self.advance();
+            self.advance();
+            let tonic = self.advance().lexeme;
+            self.advance();
+            let mode = self.advance().lexeme;
+            self.advance();
+            Ok(Box::new(ASTNode::ScaleLiteral {
+                tonic,
+                mode,
+            }))
+        } else if self.check(TokenType::Quantity) {
+            let lexeme = self.advance().lexeme;
+            let split = lexeme.find(|c: char| c.is_alphabetic() || c == '_').unwrap_or(lexeme.len());
+            Ok(Box::new(ASTNode::Quantity {
+                value: lexeme[..split].to_string(),
+                unit: lexeme[split..].to_string(),
+            }))
+        } else if self.check(TokenType::Number) || self.check(TokenType::String) {
             let value = self.advance().lexeme;
-            return Ok(Box::new(ASTNode::Literal(value)));
-        } else if self.check(TokenType::Identifier) {
+            Ok(Box::new(ASTNode::Literal(value)))
+        } else if self.check(TokenType::Identifier) || self.check(TokenType::Type) {
+            let is_type = self.check(TokenType::Type);
             let name = self.advance().lexeme;
-            if self.match_token(TokenType::LeftParen) {
-                let mut arguments = Vec::new();
-                if !self.check(TokenType::RightParen) {
-                    loop {
-                        arguments.push(*self.parse_expression()?);
-
-                        if !self.match_token(TokenType::Comma) {
-                            break;
-                        }
-                    }
-                }
-
-                self.consume(TokenType::RightParen, "Expected ')' after function arguments")?;
-                return Ok(Box::new(ASTNode::FunctionCall {
-                    function: name,
-                    arguments,
+            // `BASE { ... }` after `with tempo` or `every` is a constant and a block
+            if is_type && self.check(TokenType::LeftBrace) && self.is_object_start() {
+                self.advance();
+                let fields = self.parse_object_properties()?;
+                return Ok(Box::new(ASTNode::RecordLiteral {
+                    type_name: name,
+                    fields,
+                }));
+            }
+            if self.match_token(TokenType::DoubleColon) {
+                let variant = self.consume(TokenType::Type, "Expected variant name after '::'")?;
+                return Ok(Box::new(ASTNode::VariantPath {
+                    enum_name: name,
+                    variant: variant.lexeme,
                 }));
-            } else {
-                return Ok(Box::new(ASTNode::Identifier(name)));
             }
+            Ok(Box::new(ASTNode::Identifier(name)))
+        } else if self.check(TokenType::Slash) && self.peek_next().token_type == TokenType::LeftParen {
+            self.advance();
+            self.parse_lambda()
+        } else if self.check(TokenType::Slash) && self.peek_next().token_type == TokenType::Identifier {
+            self.advance();
+            let name = self.consume(TokenType::Identifier, "Expected function name after '/'")?;
+            self.consume(TokenType::Slash, "Expected '/' after function name")?;
+            Ok(Box::new(ASTNode::FunctionReference(name.lexeme)))
+        } else if self.match_token(TokenType::LeftParen) {
+            let expr = self.parse_expression()?;
+            self.consume(TokenType::RightParen, "Expected ')' after expression")?;
+            Ok(expr)
         } else {
-            return Err(format!("Expected expression, got {:?}", self.peek()));
+            Err(format!("Expected expression, got {:?}", self.peek()))
         }
     }
 
+    // `{ }` or `{ key: ...`; any other `{` opens a block.
+    fn is_object_start(&self) -> bool {
+        self.peek_next().token_type == TokenType::RightBrace ||
+            (matches!(self.peek_next().token_type,
+                      TokenType::Identifier | TokenType::Type | TokenType::String | TokenType::Number) &&
+             self.peek_ahead(2).token_type == TokenType::Colon)
+    }
+
+    // `<` can't start an expression anywhere else, so in primary position it
+    // opens a chord. Notes are parsed below the comparison level, which lets
+    // the closing `>` end the chord instead of being read as greater-than.
+    fn parse_chord(&mut self) -> Result<Box<ASTNode>, String> {
+        let mut notes = Vec::new();
+        while !self.check(TokenType::GreaterThan) && !self.is_at_end() {
+            notes.push(*self.parse_precedence(PREFIX_PRECEDENCE)?);
+        }
+        self.consume(TokenType::GreaterThan, "Expected '>' after chord notes")?;
+
+        if notes.is_empty() {
+            return Err(String::from("Chord literal needs at least one note"));
+        }
+
+        Ok(Box::new(ASTNode::ChordLiteral(notes)))
+    }
+
+    // `scale(D, dorian)`: a pitch name and a mode name, anything else is a call.
+    fn is_scale_literal(&self) -> bool {
+        self.check(TokenType::Identifier) &&
+            self.peek().lexeme == "scale" &&
+            self.peek_next().token_type == TokenType::LeftParen &&
+            self.peek_ahead(2).token_type == TokenType::Type &&
+            self.peek_ahead(3).token_type == TokenType::Comma &&
+            self.peek_ahead(4).token_type == TokenType::Identifier &&
+            self.peek_ahead(5).token_type == TokenType::RightParen
+    }
+
     fn parse_array(&mut self) -> Result<Box<ASTNode>, String> {
         let mut elements = Vec::new();
         if !self.check(TokenType::RightBracket) {
@@ -435,14 +955,28 @@ impl Parser {
     }
 
     fn parse_object(&mut self) -> Result<Box<ASTNode>, String> {
+        let properties = self.parse_object_properties()?;
+        Ok(Box::new(ASTNode::ObjectLiteral(properties)))
+    }
+
+    fn parse_object_properties(&mut self) -> Result<Vec<(String, ASTNode)>, String> {
         let mut properties = Vec::new();
         if !self.check(TokenType::RightBrace) {
             loop {
-                let key = self.consume(TokenType::Identifier, "Expected property name")?;
+                let key = if self.check(TokenType::String) {
+                    let lexeme = self.advance().lexeme;
+                    lexeme[1..lexeme.len() - 1].to_string()
+                } else if self.check(TokenType::Identifier) ||
+                          self.check(TokenType::Type) ||
+                          self.check(TokenType::Number) {
+                    self.advance().lexeme
+                } else {
+                    return Err(format!("Expected property name - got {:?} instead", self.peek().token_type));
+                };
                 self.consume(TokenType::Colon, "Expected ':' after property name")?;
 
                 let value = self.parse_expression()?;
-                properties.push((key.lexeme, *value));
+                properties.push((key, *value));
 
                 if !self.match_token(TokenType::Comma) {
                     break;
@@ -451,23 +985,132 @@ impl Parser {
         }
 
         self.consume(TokenType::RightBrace, "Expected '}' after object properties")?;
-        Ok(Box::new(ASTNode::ObjectLiteral(properties)))
+        Ok(properties)
     }
 
     fn parse_function_declaration(&mut self) -> Result<ASTNode, String> {
         let name = self.consume(TokenType::Identifier, "Expected function name after '/'")?;
         
         self.consume(TokenType::Slash, "Expected '/' after function name")?;
+        let type_params = self.parse_type_parameters()?;
         self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
         
-        let mut params = Vec::new();
+        let params = self.parse_parameters()?;
+        let return_type = self.parse_return_type()?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before function body")?;
+        let body = self.parse_function_body()?;
+        
+        Ok(ASTNode::FunctionDeclaration {
+            attributes: Vec::new(),
+            name: name.lexeme,
+            type_params,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn parse_lambda(&mut self) -> Result<Box<ASTNode>, String> {
+        self.consume(TokenType::LeftParen, "Expected '(' after '/'")?;
+
+        let params = self.parse_parameters()?;
+        let return_type = if self.check(TokenType::Arrow) {
+            None
+        } else {
+            Some(self.parse_return_type()?)
+        };
+
+        let body = if self.match_token(TokenType::Arrow) {
+            vec![ASTNode::ReturnStatement(Some(self.parse_expression()?))]
+        } else {
+            self.consume(TokenType::LeftBrace, "Expected '{' or '=>' before lambda body")?;
+            self.parse_function_body()?
+        };
+
+        Ok(Box::new(ASTNode::Lambda {
+            params,
+            return_type,
+            body,
+            captures: Vec::new(),
+        }))
+    }
+
+    // A missing return type, right before the body, means `Void`.
+    fn parse_return_type(&mut self) -> Result<TypeExpr, String> {
+        if self.check(TokenType::LeftBrace) || self.check(TokenType::Semicolon) {
+            return Ok(TypeExpr::Named(String::from("Void")));
+        }
+        self.parse_type()
+    }
+
+    fn parse_type_parameters(&mut self) -> Result<Vec<TypeParameter>, String> {
+        let mut type_params = Vec::new();
+        if !self.match_token(TokenType::LessThan) {
+            return Ok(type_params);
+        }
+
+        loop {
+            let name = self.consume(TokenType::Type, "Expected type parameter name")?;
+
+            let mut bounds = Vec::new();
+            if self.match_token(TokenType::Colon) {
+                loop {
+                    bounds.push(self.parse_type()?);
+
+                    if !self.match_token(TokenType::Plus) {
+                        break;
+                    }
+                }
+            }
+            type_params.push(TypeParameter {
+                name: name.lexeme,
+                bounds,
+            });
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::GreaterThan, "Expected '>' after type parameters")?;
+
+        Ok(type_params)
+    }
+
+    fn parse_parameters(&mut self) -> Result<Vec<Parameter>, String> {
+        let mut params: Vec<Parameter> = Vec::new();
         
         if !self.check(TokenType::RightParen) {
             loop {
-                let param_type = self.consume(TokenType::Type, "Expected parameter type")?;
+                let param_type = self.parse_type()?;
+                let variadic = self.match_token(TokenType::Ellipsis);
                 let param_name = self.consume(TokenType::Identifier, "Expected parameter name")?;
+                let default = if self.match_token(TokenType::Equal) {
+                    Some(self.parse_expression()?)
+                } else {
+                    None
+                };
+
+                if let Some(last) = params.last() {
+                    if last.variadic {
+                        return Err(format!("Variadic parameter '{}' must be the last parameter", last.name));
+                    }
+                    if last.default.is_some() && default.is_none() && !variadic {
+                        return Err(format!("Parameter '{}' needs a default value because '{}' has one", param_name.lexeme, last.name));
+                    }
+                }
+                if variadic && default.is_some() {
+                    return Err(format!("Variadic parameter '{}' cannot have a default value", param_name.lexeme));
+                }
+                if params.iter().any(|param| param.name == param_name.lexeme) {
+                    return Err(format!("Duplicate parameter '{}'", param_name.lexeme));
+                }
                 
-                params.push((param_name.lexeme, param_type.lexeme));
+                params.push(Parameter {
+                    name: param_name.lexeme,
+                    param_type,
+                    default,
+                    variadic,
+                });
                 
                 if !self.match_token(TokenType::Comma) {
                     break;
@@ -476,21 +1119,17 @@ impl Parser {
         }
         
         self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
-        let return_type = self.consume(TokenType::Type, "Expected return type")?; 
-        self.consume(TokenType::LeftBrace, "Expected '{' before function body")?;
+        Ok(params)
+    }
+
+    fn parse_function_body(&mut self) -> Result<Vec<ASTNode>, String> {
         let mut body = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
             body.push(self.parse_statement()?);
         }
         
         self.consume(TokenType::RightBrace, "Expected '}' after function body")?;
-        
-        Ok(ASTNode::FunctionDeclaration {
-            name: name.lexeme,
-            params,
-            return_type: return_type.lexeme,
-            body,
-        })
+        Ok(body)
     }
 
     fn parse_if_statement(&mut self) -> Result<ASTNode, String> {
@@ -590,23 +1229,21 @@ impl Parser {
         }
         self.consume(TokenType::RightBrace, "Expected '}' after try block")?;
 
-        self.consume(TokenType::Catch, "Expected 'catch' after try block")?;
-        self.consume(TokenType::LeftParen, "Expected '(' after 'catch'")?;
-
-        let mut catch_variable = None;
-        if self.check(TokenType::Type) {
-            self.advance();
-            let error_var = self.consume(TokenType::Identifier, "Expected error variable name")?;
-            catch_variable = Some(error_var.lexeme);
+        let mut catch_clauses: Vec<CatchClause> = Vec::new();
+        while self.match_token(TokenType::Catch) {
+            if catch_clauses.last().is_some_and(|clause| clause.error_type.is_none()) {
+                return Err(String::from("Catch-all clause must be the last catch clause"));
+            }
+            let clause = self.parse_catch_clause()?;
+            if let Some(error_type) = &clause.error_type &&
+                catch_clauses.iter().any(|earlier| earlier.error_type.as_ref() == Some(error_type)) {
+                return Err(format!("Duplicate catch clause for '{}'", error_type));
+            }
+            catch_clauses.push(clause);
         }
-
-        self.consume(TokenType::RightParen, "Expected ')' after catch declaration")?;
-        self.consume(TokenType::LeftBrace, "Expected '{' before catch block")?;
-        let mut catch_block = Vec::new();
-        while !self.check(TokenType::RightBrace) &&!self.is_at_end() {
-            catch_block.push(self.parse_statement()?);
+        if catch_clauses.is_empty() {
+            return Err(format!("Expected 'catch' after try block - got {:?} instead", self.peek().token_type));
         }
-        self.consume(TokenType::RightBrace, "Expected '}' after catch block")?;
 
         let mut finally_block = None;
         if self.match_token(TokenType::Finally) {
@@ -621,25 +1258,59 @@ impl Parser {
 
         Ok(ASTNode::TryCatch {
             try_block,
-            catch_variable,
-            catch_block,
+            catch_clauses,
             finally_block,
         })
     }
 
+    fn parse_catch_clause(&mut self) -> Result<CatchClause, String> {
+        self.consume(TokenType::LeftParen, "Expected '(' after 'catch'")?;
+
+        let mut error_type = None;
+        let mut variable = None;
+        if self.check(TokenType::Type) {
+            error_type = Some(self.parse_type()?);
+            if self.check(TokenType::Identifier) {
+                variable = Some(self.advance().lexeme);
+            }
+        } else if self.check(TokenType::Identifier) {
+            let name = self.advance().lexeme;
+            if name != "_" {
+                variable = Some(name);
+            }
+        }
+
+        self.consume(TokenType::RightParen, "Expected ')' after catch declaration")?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before catch block")?;
+        let mut body = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            body.push(self.parse_statement()?);
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after catch block")?;
+
+        Ok(CatchClause {
+            error_type,
+            variable,
+            body,
+        })
+    }
+
     fn parse_class_declaration(&mut self) -> Result<ASTNode, String> {
         self.consume(TokenType::Slash, "Expected '/' after 'Class'")?;
-        let name = self.consume(TokenType::Identifier, "Expected class name")?;
+        let name = self.consume_type_name("Expected class name")?;
+        let type_params = self.parse_type_parameters()?;
         self.consume(TokenType::Slash, "Expected '/' after class name")?;
 
         let mut extends = None;
-        if self.match_token(TokenType::Identifier) && self.previous().lexeme == "extends" {
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "extends" {
+            self.advance();
             let parent = self.consume(TokenType::Type, "Expected parent class name")?;
             extends = Some(parent.lexeme);
         }
 
         let mut implements = Vec::new();
-        if self.match_token(TokenType::Identifier) && self.previous().lexeme == "implements" {
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "implements" {
+            self.advance();
             loop {
                 let interface = self.consume(TokenType::Type, "Expected interface name")?;
                 implements.push(interface.lexeme);
@@ -651,16 +1322,78 @@ impl Parser {
         }
 
         self.consume(TokenType::LeftBrace, "Expected '{' before class body")?;
+        let mut constructor = None;
         let mut methods = Vec::new();
         let mut properties = Vec::new();
 
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            if self.check(TokenType::Type) || self.check(TokenType::Identifier) {
-                let property = self.parse_variable_declaration()?;
+            let attributes = self.parse_attributes()?;
+            let visibility = if self.match_token(TokenType::Priv) {
+                Visibility::Private
+            } else {
+                self.match_token(TokenType::Pub);
+                Visibility::Public
+            };
+            let is_static = self.match_token(TokenType::Static);
+            let is_operator = self.check(TokenType::Identifier) &&
+                self.peek().lexeme == "operator" &&
+                operator_method(&self.peek_next().lexeme).is_some();
+
+            if self.match_token(TokenType::Const) {
+                if is_static {
+                    return Err(String::from("Constants are already static"));
+                }
+                let mut constant = self.parse_const_declaration(visibility)?;
+                attach_attributes(&mut constant, attributes)?;
+                properties.push(constant);
+            } else if self.check(TokenType::Identifier) && self.peek().lexeme == "new" && self.peek_next().token_type == TokenType::LeftParen {
+                if is_static {
+                    return Err(String::from("Constructor cannot be static"));
+                }
+                if constructor.is_some() {
+                    return Err(format!("Class '{}' already has a constructor", name.lexeme));
+                }
+                if !attributes.is_empty() {
+                    return Err(String::from("Attributes cannot be applied to constructors"));
+                }
+                self.advance();
+                self.advance();
+                let params = self.parse_parameters()?;
+                self.consume(TokenType::LeftBrace, "Expected '{' before constructor body")?;
+                let body = self.parse_function_body()?;
+
+                constructor = Some(Box::new(ASTNode::Constructor {
+                    visibility,
+                    params,
+                    body,
+                }));
+            } else if is_operator {
+                if is_static {
+                    return Err(format!("Operator '{}' cannot be static", self.peek_next().lexeme));
+                }
+                self.advance();
+                let mut method = self.parse_operator_declaration()?;
+                attach_attributes(&mut method, attributes)?;
+                methods.push(ASTNode::MethodDeclaration {
+                    visibility,
+                    is_static,
+                    function: Box::new(method),
+                });
+            } else if self.check(TokenType::Type) ||
+                      self.check(TokenType::Identifier) ||
+                      self.check(TokenType::LeftBracket) ||
+                      self.check(TokenType::LeftParen) {
+                let mut property = self.parse_field_declaration(visibility, is_static)?;
+                attach_attributes(&mut property, attributes)?;
                 properties.push(property);
             } else if self.match_token(TokenType::Slash) {
-                let method = self.parse_function_declaration()?;
-                methods.push(method);
+                let mut method = self.parse_function_declaration()?;
+                attach_attributes(&mut method, attributes)?;
+                methods.push(ASTNode::MethodDeclaration {
+                    visibility,
+                    is_static,
+                    function: Box::new(method),
+                });
             } else {
                 return Err(format!("Expected property or method declaration, got {:?}", self.peek()));
             }
@@ -668,14 +1401,204 @@ impl Parser {
         self.consume(TokenType::RightBrace, "Expected '}' after class body")?;
 
         Ok(ASTNode::ClassDeclaration {
+            attributes: Vec::new(),
             name: name.lexeme,
+            type_params,
             extends,
             implements,
+            constructor,
             methods,
             properties,
         })
     }
 
+    // `operator +(Interval other)Note { ... }` is sugar for `/op_add/(Interval other)Note { ... }`.
+    fn parse_operator_declaration(&mut self) -> Result<ASTNode, String> {
+        let symbol = self.advance().lexeme;
+        let name = operator_method(&symbol).ok_or_else(|| format!("'{}' is not an overloadable operator", symbol))?;
+        self.consume(TokenType::LeftParen, "Expected '(' after operator")?;
+
+        let params = self.parse_parameters()?;
+        if params.len() != 1 {
+            return Err(format!("Operator '{}' takes exactly one parameter, got {}", symbol, params.len()));
+        }
+        let return_type = self.parse_return_type()?;
+        self.consume(TokenType::LeftBrace, "Expected '{' before operator body")?;
+        let body = self.parse_function_body()?;
+
+        Ok(ASTNode::FunctionDeclaration {
+            attributes: Vec::new(),
+            name: String::from(name),
+            type_params: Vec::new(),
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn parse_field_declaration(&mut self, visibility: Visibility, is_static: bool) -> Result<ASTNode, String> {
+        let var_type = if self.check(TokenType::Identifier) {
+            TypeExpr::Named(self.advance().lexeme)
+        } else {
+            self.parse_type()?
+        };
+        self.consume(TokenType::Slash, "Expected '/' after type name")?;
+        let name = self.consume(TokenType::Identifier, "Expected field name")?;
+        self.consume(TokenType::Slash, "Expected '/' after field name")?;
+
+        let value = if self.match_token(TokenType::Equal) {
+            Some(self.parse_expression()?)
+        } else {
+            None
+        };
+        self.consume(TokenType::Semicolon, "Expected ';' after field declaration")?;
+
+        Ok(ASTNode::FieldDeclaration {
+            attributes: Vec::new(),
+            visibility,
+            is_static,
+            var_type,
+            name: name.lexeme,
+            value,
+        })
+    }
+
+    fn parse_interface_declaration(&mut self) -> Result<ASTNode, String> {
+        self.consume(TokenType::Slash, "Expected '/' after 'interface'")?;
+        let name = self.consume_type_name("Expected interface name")?;
+        let type_params = self.parse_type_parameters()?;
+        self.consume(TokenType::Slash, "Expected '/' after interface name")?;
+
+        let mut extends = Vec::new();
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "extends" {
+            self.advance();
+            loop {
+                let parent = self.consume(TokenType::Type, "Expected parent interface name")?;
+                extends.push(parent.lexeme);
+
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before interface body")?;
+        let mut methods = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            self.consume(TokenType::Slash, "Expected method declaration in interface body")?;
+            let method_name = self.consume(TokenType::Identifier, "Expected method name after '/'")?;
+            self.consume(TokenType::Slash, "Expected '/' after method name")?;
+            let type_params = self.parse_type_parameters()?;
+            self.consume(TokenType::LeftParen, "Expected '(' after method name")?;
+
+            let params = self.parse_parameters()?;
+            let return_type = self.parse_return_type()?;
+
+            if self.match_token(TokenType::Semicolon) {
+                methods.push(ASTNode::FunctionSignature {
+                    name: method_name.lexeme,
+                    type_params,
+                    params,
+                    return_type,
+                });
+            } else {
+                self.consume(TokenType::LeftBrace, "Expected ';' or '{' after method signature")?;
+                let body = self.parse_function_body()?;
+
+                methods.push(ASTNode::FunctionDeclaration {
+                    attributes: Vec::new(),
+                    name: method_name.lexeme,
+                    type_params,
+                    params,
+                    return_type,
+                    body,
+                });
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after interface body")?;
+
+        Ok(ASTNode::InterfaceDeclaration {
+            name: name.lexeme,
+            type_params,
+            extends,
+            methods,
+        })
+    }
+
+    fn parse_enum_declaration(&mut self) -> Result<ASTNode, String> {
+        self.consume(TokenType::Slash, "Expected '/' after 'enum'")?;
+        let name = self.consume_type_name("Expected enum name")?;
+        let type_params = self.parse_type_parameters()?;
+        self.consume(TokenType::Slash, "Expected '/' after enum name")?;
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before enum variants")?;
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let variant = self.consume(TokenType::Type, "Expected variant name")?;
+
+            let mut fields = Vec::new();
+            if self.match_token(TokenType::LeftParen) {
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        fields.push(self.parse_type()?);
+
+                        if !self.match_token(TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expected ')' after variant fields")?;
+            }
+
+            if variants.iter().any(|existing: &EnumVariant| existing.name == variant.lexeme) {
+                return Err(format!("Duplicate variant '{}' in enum '{}'", variant.lexeme, name.lexeme));
+            }
+            variants.push(EnumVariant {
+                name: variant.lexeme,
+                fields,
+            });
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after enum variants")?;
+
+        Ok(ASTNode::EnumDeclaration {
+            name: name.lexeme,
+            type_params,
+            variants,
+        })
+    }
+
+    fn parse_record_declaration(&mut self) -> Result<ASTNode, String> {
+        self.consume(TokenType::Slash, "Expected '/' after 'record'")?;
+        let name = self.consume_type_name("Expected record name")?;
+        let type_params = self.parse_type_parameters()?;
+        self.consume(TokenType::Slash, "Expected '/' after record name")?;
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before record fields")?;
+        let mut fields: Vec<(String, TypeExpr)> = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let field_type = self.parse_type()?;
+            let field_name = self.consume(TokenType::Identifier, "Expected field name")?;
+            self.consume(TokenType::Semicolon, "Expected ';' after record field")?;
+
+            if fields.iter().any(|(existing, _)| *existing == field_name.lexeme) {
+                return Err(format!("Duplicate field '{}' in record '{}'", field_name.lexeme, name.lexeme));
+            }
+            fields.push((field_name.lexeme, field_type));
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after record fields")?;
+
+        Ok(ASTNode::RecordDeclaration {
+            name: name.lexeme,
+            type_params,
+            fields,
+        })
+    }
+
     fn parse_block(&mut self) -> Result<ASTNode, String> {
         let mut stmts = Vec::new();
 
@@ -687,6 +1610,162 @@ impl Parser {
         Ok(ASTNode::Block(stmts))
     }
 
+    fn parse_block_expression(&mut self) -> Result<Box<ASTNode>, String> {
+        let mut statements = Vec::new();
+        let mut value = None;
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if !self.starts_expression_statement() {
+                statements.push(self.parse_statement()?);
+                continue;
+            }
+
+            let expr = self.parse_expression()?;
+            if self.match_token(TokenType::Semicolon) {
+                statements.push(ASTNode::ExpressionStatement(expr));
+            } else if self.check(TokenType::RightBrace) {
+                value = Some(expr);
+            } else if matches!(*expr,
+                ASTNode::IfExpression { .. } |
+                ASTNode::MatchExpression { .. } |
+                ASTNode::BlockExpression { .. } |
+                ASTNode::SeqBlock(_) |
+                ASTNode::ParBlock(_) |
+                ASTNode::AtBlock { .. } |
+                ASTNode::Spawn(_)) {
+                statements.push(ASTNode::ExpressionStatement(expr));
+            } else {
+                return Err(format!("Expected ';' after expression - got {:?} instead", self.peek().token_type));
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after block")?;
+
+        Ok(Box::new(ASTNode::BlockExpression {
+            statements,
+            value,
+        }))
+    }
+
+    fn starts_expression_statement(&mut self) -> bool {
+        match self.peek().token_type {
+            TokenType::Use |
+            TokenType::Pub |
+            TokenType::Return |
+            TokenType::While |
+            TokenType::For |
+            TokenType::Try |
+            TokenType::Throw |
+            TokenType::Class |
+            TokenType::Interface |
+            TokenType::Enum |
+            TokenType::Record |
+            TokenType::Let |
+            TokenType::Var |
+            TokenType::Const |
+            TokenType::At => false,
+            TokenType::Identifier if self.is_type_alias() || self.is_musical_context() => false,
+            // `spawn { ... }` is left to the expression path so it can be the value
+            TokenType::Identifier if self.is_scheduling_statement() && self.peek().lexeme != "spawn" => false,
+            TokenType::Type | TokenType::Identifier => !self.is_variable_declaration(),
+            TokenType::Slash => self.peek_next().token_type != TokenType::Identifier,
+            _ => true,
+        }
+    }
+
+    fn is_composition_start(&self) -> bool {
+        if !self.check(TokenType::Identifier) {
+            return false;
+        }
+
+        match self.peek().lexeme.as_str() {
+            "seq" | "par" => self.peek_next().token_type == TokenType::LeftBrace,
+            "at" => matches!(self.peek_next().token_type, TokenType::Number | TokenType::Quantity),
+            _ => false,
+        }
+    }
+
+    fn parse_composition(&mut self) -> Result<Box<ASTNode>, String> {
+        let keyword = self.advance().lexeme;
+
+        if keyword == "at" {
+            let offset = self.parse_expression()?;
+            self.consume(TokenType::LeftBrace, "Expected '{' after 'at' offset")?;
+            let body = self.parse_composition_body()?;
+
+            return Ok(Box::new(ASTNode::AtBlock {
+                offset,
+                body,
+            }));
+        }
+
+        self.consume(TokenType::LeftBrace, "Expected '{' after composition keyword")?;
+        let body = self.parse_composition_body()?;
+
+        if keyword == "par" {
+            Ok(Box::new(ASTNode::ParBlock(body)))
+        } else {
+            Ok(Box::new(ASTNode::SeqBlock(body)))
+        }
+    }
+
+    // Each entry is a nested seq/par/at block or `note [duration];`.
+    fn parse_composition_body(&mut self) -> Result<Vec<ASTNode>, String> {
+        let mut entries = Vec::new();
+
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            // inside a composition `at` always starts a block, whatever the offset
+            let at_block = self.check(TokenType::Identifier) && self.peek().lexeme == "at";
+
+            if at_block || self.is_composition_start() {
+                entries.push(*self.parse_composition()?);
+                self.match_token(TokenType::Semicolon);
+            } else {
+                let note = self.parse_expression()?;
+                let duration = if self.check(TokenType::Semicolon) || self.check(TokenType::RightBrace) {
+                    None
+                } else {
+                    Some(self.parse_expression()?)
+                };
+                if !self.check(TokenType::RightBrace) {
+                    self.consume(TokenType::Semicolon, "Expected ';' after note event")?;
+                }
+
+                entries.push(ASTNode::NoteEvent {
+                    note,
+                    duration,
+                });
+            }
+        }
+        self.consume(TokenType::RightBrace, "Expected '}' after composition block")?;
+
+        Ok(entries)
+    }
+
+    fn parse_if_expression(&mut self) -> Result<Box<ASTNode>, String> {
+        self.consume(TokenType::LeftParen, "Expected '(' after 'if'")?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after if condition")?;
+
+        self.consume(TokenType::LeftBrace, "Expected '{' before if branch")?;
+        let then_branch = self.parse_block_expression()?;
+
+        let mut else_branch = None;
+        if self.match_token(TokenType::Else) {
+            if self.match_token(TokenType::If) {
+                else_branch = Some(self.parse_if_expression()?);
+            } else {
+                self.consume(TokenType::LeftBrace, "Expected '{' before else branch")?;
+                else_branch = Some(self.parse_block_expression()?);
+            }
+        }
+
+        Ok(Box::new(ASTNode::IfExpression {
+            condition,
+            then_branch,
+            else_branch,
+        }))
+    }
+
     fn parse_match_expression(&mut self) -> Result<ASTNode, String> {
         self.consume(TokenType::LeftParen, "Expected '(' after 'match'")?;
         let expression = self.parse_expression()?;
@@ -696,17 +1775,26 @@ impl Parser {
 
         let mut cases = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            let pattern = self.parse_expression()?;
+            let pattern = self.parse_pattern()?;
+
+            let guard = if self.match_token(TokenType::If) {
+                Some(self.parse_expression()?)
+            } else {
+                None
+            };
             self.consume(TokenType::Arrow, "Expected '=>' after match pattern")?;
 
-            self.consume(TokenType::LeftBrace, "Expected '{' before case body")?;
-            let mut body = Vec::new();
-            while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-                body.push(self.parse_statement()?);
-            }
-            self.consume(TokenType::RightBrace, "Expected '}' after case body")?;
+            let body = if self.match_token(TokenType::LeftBrace) {
+                self.parse_block_expression()?
+            } else {
+                self.parse_expression()?
+            };
 
-            cases.push((*pattern, body));
+            cases.push(MatchCase {
+                pattern,
+                guard,
+                body,
+            });
             self.match_token(TokenType::Comma);
         }
         self.consume(TokenType::RightBrace, "Expected '}' after match cases")?;
@@ -717,8 +1805,123 @@ impl Parser {
         })
     }
 
+    fn parse_pattern(&mut self) -> Result<Pattern, String> {
+        if self.match_token(TokenType::LeftBracket) {
+            let mut elements = Vec::new();
+            if !self.check(TokenType::RightBracket) {
+                loop {
+                    if self.match_token(TokenType::DotDot) {
+                        let rest = if self.check(TokenType::Identifier) {
+                            Some(self.advance().lexeme)
+                        } else {
+                            None
+                        };
+                        elements.push(Pattern::Rest(rest));
+                    } else {
+                        elements.push(self.parse_pattern()?);
+                    }
+
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBracket, "Expected ']' after array pattern")?;
+
+            if elements.iter().filter(|element| matches!(element, Pattern::Rest(_))).count() > 1 {
+                return Err(String::from("Array pattern can only have one '..' rest element"));
+            }
+            return Ok(Pattern::Array(elements));
+        }
+
+        if self.match_token(TokenType::LeftBrace) {
+            let mut fields = Vec::new();
+            if !self.check(TokenType::RightBrace) {
+                loop {
+                    let key = self.consume(TokenType::Identifier, "Expected field name in object pattern")?;
+                    let pattern = if self.match_token(TokenType::Colon) {
+                        self.parse_pattern()?
+                    } else {
+                        Pattern::Binding(key.lexeme.clone())
+                    };
+                    fields.push((key.lexeme, pattern));
+
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightBrace, "Expected '}' after object pattern")?;
+            return Ok(Pattern::Object(fields));
+        }
+
+        if self.check(TokenType::Type) && self.peek_next().token_type == TokenType::DoubleColon {
+            let enum_name = self.advance().lexeme;
+            self.advance();
+            let variant = self.consume(TokenType::Type, "Expected variant name after '::'")?;
+
+            let mut fields = Vec::new();
+            if self.match_token(TokenType::LeftParen) {
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        fields.push(self.parse_pattern()?);
+
+                        if !self.match_token(TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expected ')' after variant pattern fields")?;
+            }
+
+            return Ok(Pattern::Variant {
+                enum_name,
+                variant: variant.lexeme,
+                fields,
+            });
+        }
+
+        if self.check(TokenType::Identifier) && !matches!(self.peek().lexeme.as_str(), "true" | "false") {
+            let name = self.advance().lexeme;
+            if name == "_" {
+                return Ok(Pattern::Wildcard);
+            }
+            return Ok(Pattern::Binding(name));
+        }
+
+        let start = self.parse_pattern_literal()?;
+        if self.match_token(TokenType::DotDot) || self.match_token(TokenType::DotDotEqual) {
+            let inclusive = self.previous().token_type == TokenType::DotDotEqual;
+            let end = self.parse_pattern_literal()?;
+
+            return Ok(Pattern::Range {
+                start,
+                end,
+                inclusive,
+            });
+        }
+        Ok(Pattern::Literal(start))
+    }
+
+    // Pitches (`C4`), chord symbols and constants are compared by value, like
+    // numbers, strings and booleans.
+    fn parse_pattern_literal(&mut self) -> Result<String, String> {
+        if self.match_token(TokenType::Minus) {
+            let number = self.consume(TokenType::Number, "Expected number after '-' in pattern")?;
+            return Ok(format!("-{}", number.lexeme));
+        }
+        if self.check(TokenType::Number) ||
+            self.check(TokenType::String) ||
+            self.check(TokenType::Type) ||
+            self.check(TokenType::ChordSymbol) ||
+            (self.check(TokenType::Identifier) && matches!(self.peek().lexeme.as_str(), "true" | "false")) {
+            return Ok(self.advance().lexeme);
+        }
+        Err(format!("Expected pattern, got {:?}", self.peek()))
+    }
+
     fn parse_type_cast(&mut self) -> Result<ASTNode, String> {
-        let target_type = self.consume(TokenType::Type, "Expected type name")?;
+        let target_type = self.parse_type()?;
         self.consume(TokenType::Slash, "Expected '/' after type name")?;
         self.consume(TokenType::LeftParen, "Expected '(' after type cats")?;
         let expression = self.parse_expression()?;
@@ -726,9 +1929,91 @@ impl Parser {
 
         Ok(ASTNode::TypeCast {
             expression,
-            target_type: target_type.lexeme,
+            target_type,
         })
     }
+
+    // Types only show up in declaration positions, so `<` after a type name is
+    // always a generic argument list here. Statements and casts that start with
+    // a type try this first and rewind if it doesn't pan out.
+    fn parse_type(&mut self) -> Result<TypeExpr, String> {
+        let mut type_expr = if self.match_token(TokenType::LeftBracket) {
+            let element = self.parse_type()?;
+            self.consume(TokenType::RightBracket, "Expected ']' after array element type")?;
+            TypeExpr::Array(Box::new(element))
+        } else if self.match_token(TokenType::LeftParen) {
+            let mut elements = Vec::new();
+            let mut trailing_comma = false;
+            if !self.check(TokenType::RightParen) {
+                loop {
+                    elements.push(self.parse_type()?);
+
+                    trailing_comma = self.match_token(TokenType::Comma);
+                    if !trailing_comma || self.check(TokenType::RightParen) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenType::RightParen, "Expected ')' after type list")?;
+
+            if self.match_token(TokenType::ThinArrow) {
+                let return_type = self.parse_type()?;
+                TypeExpr::Function {
+                    params: elements,
+                    return_type: Box::new(return_type),
+                }
+            } else if elements.len() == 1 && !trailing_comma {
+                elements.remove(0)
+            } else {
+                TypeExpr::Tuple(elements)
+            }
+        } else {
+            let name = self.consume(TokenType::Type, "Expected type name")?;
+
+            if self.match_token(TokenType::LessThan) {
+                let mut arguments = Vec::new();
+                loop {
+                    arguments.push(self.parse_type()?);
+
+                    if !self.match_token(TokenType::Comma) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::GreaterThan, "Expected '>' after type arguments")?;
+
+                TypeExpr::Generic {
+                    name: name.lexeme,
+                    arguments,
+                }
+            } else {
+                TypeExpr::Named(name.lexeme)
+            }
+        };
+
+        while self.match_token(TokenType::Question) {
+            type_expr = TypeExpr::Optional(Box::new(type_expr));
+        }
+        Ok(type_expr)
+    }
+
+    // `Type/name/ =`; anything shorter (`x / 2`, `Float/(x)`) is an expression.
+    fn is_variable_declaration(&mut self) -> bool {
+        let start = self.current;
+        let has_type = if self.check(TokenType::Identifier) {
+            self.advance();
+            true
+        } else {
+            self.parse_type().is_ok()
+        };
+        let is_declaration = has_type &&
+            self.check(TokenType::Slash) &&
+            self.peek_next().token_type == TokenType::Identifier &&
+            self.peek_ahead(2).token_type == TokenType::Slash &&
+            self.peek_ahead(3).token_type == TokenType::Equal;
+        self.current = start;
+
+        is_declaration
+    }
     
     fn advance(&mut self) -> Token {
         if !self.is_at_end() {
@@ -737,6 +2022,14 @@ impl Parser {
         self.previous()
     }
     
+    fn consume_type_name(&mut self, error_message: &str) -> Result<Token, String> {
+        if self.check(TokenType::Type) {
+            Ok(self.advance())
+        } else {
+            self.consume(TokenType::Identifier, error_message)
+        }
+    }
+    
     fn consume(&mut self, token_type: TokenType, error_message: &str) -> Result<Token, String> {
         if self.check(token_type) {
             Ok(self.advance())
@@ -774,6 +2067,14 @@ impl Parser {
         }
     }
     
+    fn peek_ahead(&self, distance: usize) -> &Token {
+        if self.current + distance >= self.tokens.len() {
+            &self.tokens[self.tokens.len() - 1]
+        } else {
+            &self.tokens[self.current + distance]
+        }
+    }
+    
     fn previous(&self) -> Token {
         self.tokens[self.current - 1].clone()
     }
@@ -782,3 +2083,23 @@ impl Parser {
         self.peek().token_type == TokenType::EOF
     }
 }
+
+// `tempo 120bpm`, `tempo BASE * 2`; `tempo(x)` and `tempo = x` stay ordinary
+// uses of a variable called `tempo`.
+fn is_tempo_start(token_type: &TokenType) -> bool {
+    matches!(token_type, TokenType::Number | TokenType::Quantity | TokenType::Identifier | TokenType::Type)
+}
+
+fn attach_attributes(declaration: &mut ASTNode, attributes: Vec<Attribute>) -> Result<(), String> {
+    match declaration {
+        ASTNode::FunctionDeclaration { attributes: target, .. } |
+        ASTNode::ClassDeclaration { attributes: target, .. } |
+        ASTNode::VariableDeclaration { attributes: target, .. } |
+        ASTNode::ConstDeclaration { attributes: target, .. } |
+        ASTNode::FieldDeclaration { attributes: target, .. } => {
+            *target = attributes;
+            Ok(())
+        }
+        _ => Err(String::from("Attributes can only be applied to functions, classes, variables, constants and fields")),
+    }
+}
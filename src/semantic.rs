@@ -0,0 +1,1115 @@
+use crate::ast::{operator_method, ASTNode, Overload, Parameter, Pattern, TypeExpr};
+use std::collections::{HashMap, HashSet};
+
+// `imports` are the ASTs of the modules `program` uses; their records and
+// constants count as declared.
+pub fn check(program: &ASTNode, imports: &[&ASTNode]) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    check_record_literals(program, imports, &mut errors);
+    check_constants(program, imports, &mut errors);
+    check_calls(program, &mut errors);
+    check_returns(program, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_record_literals(program: &ASTNode, imports: &[&ASTNode], errors: &mut Vec<String>) {
+    let mut records: HashMap<&str, &Vec<(String, TypeExpr)>> = HashMap::new();
+    for module in imports.iter().copied().chain(std::iter::once(program)) {
+        walk(module, &mut |node| {
+            if let ASTNode::RecordDeclaration { name, fields, .. } = node {
+                records.insert(name, fields);
+            }
+        });
+    }
+
+    walk(program, &mut |node| {
+        let ASTNode::RecordLiteral { type_name, fields } = node else {
+            return;
+        };
+        let Some(declared) = records.get(type_name.as_str()) else {
+            errors.push(format!("'{}' is not a record type", type_name));
+            return;
+        };
+
+        for (index, (field, _)) in fields.iter().enumerate() {
+            if fields[..index].iter().any(|(earlier, _)| earlier == field) {
+                errors.push(format!("Field '{}' is given more than once in '{}' literal", field, type_name));
+            } else if !declared.iter().any(|(name, _)| name == field) {
+                errors.push(format!("Record '{}' has no field '{}'", type_name, field));
+            }
+        }
+        for (name, _) in declared.iter() {
+            if !fields.iter().any(|(field, _)| field == name) {
+                errors.push(format!("Missing field '{}' in '{}' literal", name, type_name));
+            }
+        }
+    });
+}
+
+// Checks arity and argument names of calls to the functions, constructors and
+// static methods declared at the top level of this module.
+fn check_calls(program: &ASTNode, errors: &mut Vec<String>) {
+    let mut callables: HashMap<String, (String, &[Parameter])> = HashMap::new();
+    let ASTNode::Program(statements) = program else {
+        return;
+    };
+    for statement in statements {
+        match statement {
+            ASTNode::FunctionDeclaration { name, params, .. } => {
+                callables.insert(name.clone(), (format!("function '{}'", name), params));
+            }
+            ASTNode::ClassDeclaration { name, constructor, methods, .. } => {
+                if let Some(constructor) = constructor.as_deref() &&
+                    let ASTNode::Constructor { params, .. } = constructor {
+                    callables.insert(name.clone(), (format!("'{}' constructor", name), params));
+                }
+                for method in methods {
+                    if let ASTNode::MethodDeclaration { is_static: true, function, .. } = method &&
+                        let ASTNode::FunctionDeclaration { name: method_name, params, .. } = function.as_ref() {
+                        let key = format!("{}.{}", name, method_name);
+                        callables.insert(key.clone(), (format!("method '{}'", key), params));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    walk(program, &mut |node| {
+        let (key, arguments) = match node {
+            ASTNode::FunctionCall { callee, arguments, .. } => match callee.as_ref() {
+                ASTNode::Identifier(name) => (name.clone(), arguments),
+                _ => return,
+            },
+            ASTNode::MethodCall { object, method, arguments, .. } => match object.as_ref() {
+                ASTNode::Identifier(class_name) => (format!("{}.{}", class_name, method), arguments),
+                _ => return,
+            },
+            _ => return,
+        };
+        if let Some((label, params)) = callables.get(&key) {
+            check_arguments(label, params, arguments, errors);
+        }
+    });
+}
+
+// Positional arguments fill the fixed parameters in order and the rest go to
+// the variadic one; named arguments then fill what is left.
+fn check_arguments(label: &str, params: &[Parameter], arguments: &[ASTNode], errors: &mut Vec<String>) {
+    let fixed = params.iter().filter(|param| !param.variadic).count();
+    let has_variadic = params.iter().any(|param| param.variadic);
+    let positional = arguments.iter().filter(|argument| !matches!(argument, ASTNode::NamedArgument { .. })).count();
+
+    if positional > fixed && !has_variadic {
+        errors.push(format!("Too many arguments for {}: expected at most {}, got {}", label, fixed, positional));
+    }
+
+    let mut bound: Vec<bool> = (0..params.len()).map(|index| index < positional.min(fixed)).collect();
+    for argument in arguments {
+        let ASTNode::NamedArgument { name, .. } = argument else {
+            continue;
+        };
+        match params.iter().position(|param| param.name == *name) {
+            None => errors.push(format!("No parameter named '{}' in {}", name, label)),
+            Some(index) if params[index].variadic => {
+                errors.push(format!("Variadic parameter '{}' of {} cannot be passed by name", name, label));
+            }
+            Some(index) if bound[index] => {
+                errors.push(format!("Parameter '{}' of {} is given both positionally and by name", name, label));
+            }
+            Some(index) => bound[index] = true,
+        }
+    }
+
+    for (param, bound) in params.iter().zip(bound) {
+        if !bound && !param.variadic && param.default.is_none() {
+            errors.push(format!("Missing argument for parameter '{}' of {}", param.name, label));
+        }
+    }
+}
+
+// A function or lambda with a declared non-`Void` return type has to end every path
+// with `return value;` (bare `return;` doesn't count).
+fn check_returns(program: &ASTNode, errors: &mut Vec<String>) {
+    walk(program, &mut |node| {
+        let (label, return_type, body) = match node {
+            ASTNode::FunctionDeclaration { name, return_type, body, .. } => (format!("Function '{}'", name), return_type, body),
+            ASTNode::Lambda { return_type: Some(return_type), body, .. } => (String::from("Lambda"), return_type, body),
+            _ => return,
+        };
+        if *return_type == TypeExpr::Named(String::from("Void")) {
+            return;
+        }
+
+        if body.iter().any(has_bare_return) {
+            errors.push(format!("{} returns '{}' but has a bare 'return;'", label, return_type));
+        } else if !always_returns(body) {
+            errors.push(format!("{} returns '{}' but not every path ends in a return", label, return_type));
+        }
+    });
+}
+
+fn always_returns(statements: &[ASTNode]) -> bool {
+    statements.iter().any(|statement| match statement {
+        ASTNode::ReturnStatement(value) => value.is_some(),
+        ASTNode::Block(body) |
+        ASTNode::WithContext { body, .. } => always_returns(body),
+        ASTNode::IfStatement { then_branch, else_branch: Some(else_branch), .. } => {
+            always_returns(then_branch) && always_returns(else_branch)
+        }
+        ASTNode::ThrowStatement(_) => true,
+        ASTNode::TryCatch { try_block, catch_clauses, finally_block } => {
+            (always_returns(try_block) && catch_clauses.iter().all(|clause| always_returns(&clause.body))) ||
+                finally_block.as_deref().is_some_and(always_returns)
+        }
+        ASTNode::ExpressionStatement(expression) => match expression.as_ref() {
+            // every case returns and one of them catches whatever is left
+            ASTNode::MatchExpression { cases, .. } => {
+                cases.iter().all(|case| match case.body.as_ref() {
+                    ASTNode::BlockExpression { statements, .. } => always_returns(statements),
+                    _ => false,
+                }) && cases.iter().any(|case| {
+                    case.guard.is_none() && matches!(case.pattern, Pattern::Wildcard | Pattern::Binding(_))
+                })
+            }
+            _ => false,
+        },
+        _ => false,
+    })
+}
+
+fn has_bare_return(node: &ASTNode) -> bool {
+    match node {
+        ASTNode::ReturnStatement(None) => true,
+        // nested functions and spawned tasks have their own return type
+        ASTNode::FunctionDeclaration { .. } |
+        ASTNode::Lambda { .. } |
+        ASTNode::Spawn(_) |
+        ASTNode::ClassDeclaration { .. } => false,
+        _ => children(node).into_iter().any(has_bare_return),
+    }
+}
+
+// Fills in the `captures` list of every lambda: the names it uses that are
+// bound in an enclosing function or lambda. Top-level bindings are globals
+// and never captured.
+pub fn resolve_captures(program: &mut ASTNode) {
+    let mut resolver = CaptureResolver {
+        scopes: vec![HashSet::new()],
+        frames: Vec::new(),
+    };
+    resolver.visit(program);
+}
+
+struct LambdaFrame {
+    scope_depth: usize,
+    captures: Vec<String>,
+}
+
+struct CaptureResolver {
+    scopes: Vec<HashSet<String>>,
+    frames: Vec<LambdaFrame>,
+}
+
+impl CaptureResolver {
+    fn declare(&mut self, name: &str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    fn reference(&mut self, name: &str) {
+        let Some(depth) = self.scopes.iter().rposition(|scope| scope.contains(name)) else {
+            return;
+        };
+        if depth == 0 {
+            return;
+        }
+
+        // every lambda between the use and the binding has to carry the value in
+        for frame in self.frames.iter_mut().rev() {
+            if frame.scope_depth <= depth {
+                break;
+            }
+            if !frame.captures.iter().any(|captured| captured == name) {
+                frame.captures.push(name.to_string());
+            }
+        }
+    }
+
+    fn visit_scoped(&mut self, nodes: &mut [ASTNode], bindings: &[String]) {
+        self.scopes.push(bindings.iter().cloned().collect());
+        for node in nodes.iter_mut() {
+            self.visit(node);
+        }
+        self.scopes.pop();
+    }
+
+    // defaults are evaluated where the function is defined, outside its own scope
+    fn visit_defaults(&mut self, params: &mut [Parameter]) {
+        for param in params.iter_mut() {
+            if let Some(default) = &mut param.default {
+                self.visit(default);
+            }
+        }
+    }
+
+    fn visit(&mut self, node: &mut ASTNode) {
+        match node {
+            ASTNode::Program(statements) => {
+                for statement in statements.iter_mut() {
+                    self.visit(statement);
+                }
+            }
+            ASTNode::UseStatement { .. } |
+            ASTNode::MeterDeclaration { .. } |
+            ASTNode::KeyDeclaration { .. } |
+            ASTNode::Quantity { .. } |
+            ASTNode::ChordSymbol { .. } |
+            ASTNode::ScaleLiteral { .. } |
+            ASTNode::Literal(_) |
+            ASTNode::FunctionReference(_) => {}
+            ASTNode::Identifier(name) => {
+                let name = name.clone();
+                self.reference(&name);
+            }
+            ASTNode::ReturnStatement(value) => {
+                if let Some(value) = value {
+                    self.visit(value);
+                }
+            }
+            ASTNode::TempoDeclaration(value) |
+            ASTNode::ThrowStatement(value) |
+            ASTNode::WaitStatement(value) |
+            ASTNode::Await(value) |
+            ASTNode::NamedArgument { value, .. } |
+            ASTNode::ExpressionStatement(value) => self.visit(value),
+            ASTNode::WithContext { settings, body } => {
+                for setting in settings.iter_mut() {
+                    self.visit(setting);
+                }
+                self.visit_scoped(body, &[]);
+            }
+            ASTNode::VariableDeclaration { name, value, .. } |
+            ASTNode::ConstDeclaration { name, value, .. } => {
+                self.visit(value);
+                let name = name.clone();
+                self.declare(&name);
+            }
+            ASTNode::FunctionDeclaration { name, params, body, .. } => {
+                let name = name.clone();
+                self.declare(&name);
+                self.visit_defaults(params);
+                let bindings: Vec<String> = params.iter().map(|param| param.name.clone()).collect();
+                self.visit_scoped(body, &bindings);
+            }
+            ASTNode::Lambda { params, body, captures, .. } => {
+                self.visit_defaults(params);
+                self.frames.push(LambdaFrame {
+                    scope_depth: self.scopes.len(),
+                    captures: Vec::new(),
+                });
+                let bindings: Vec<String> = params.iter().map(|param| param.name.clone()).collect();
+                self.visit_scoped(body, &bindings);
+                if let Some(frame) = self.frames.pop() {
+                    *captures = frame.captures;
+                }
+            }
+            ASTNode::ArrayLiteral(elements) |
+            ASTNode::ChordLiteral(elements) => {
+                for element in elements.iter_mut() {
+                    self.visit(element);
+                }
+            }
+            ASTNode::ObjectLiteral(properties) |
+            ASTNode::RecordLiteral { fields: properties, .. } => {
+                for (_, value) in properties.iter_mut() {
+                    self.visit(value);
+                }
+            }
+            ASTNode::PropertyAccess { object, .. } => self.visit(object),
+            ASTNode::FunctionCall { callee, arguments, .. } => {
+                self.visit(callee);
+                for argument in arguments.iter_mut() {
+                    self.visit(argument);
+                }
+            }
+            ASTNode::MethodCall { object, arguments, .. } => {
+                self.visit(object);
+                for argument in arguments.iter_mut() {
+                    self.visit(argument);
+                }
+            }
+            ASTNode::BinaryExpression { left, right, .. } => {
+                self.visit(left);
+                self.visit(right);
+            }
+            ASTNode::IndexExpression { object, index } => {
+                self.visit(object);
+                self.visit(index);
+            }
+            ASTNode::SliceExpression { object, start, end } => {
+                self.visit(object);
+                if let Some(start) = start {
+                    self.visit(start);
+                }
+                if let Some(end) = end {
+                    self.visit(end);
+                }
+            }
+            ASTNode::Assignment { target, value } => {
+                self.visit(target);
+                self.visit(value);
+            }
+            ASTNode::IfStatement { condition, then_branch, else_branch } => {
+                self.visit(condition);
+                self.visit_scoped(then_branch, &[]);
+                if let Some(else_branch) = else_branch {
+                    self.visit_scoped(else_branch, &[]);
+                }
+            }
+            ASTNode::WhileLoop { condition, body } => {
+                self.visit(condition);
+                self.visit_scoped(body, &[]);
+            }
+            ASTNode::ForLoop { initializer, condition, increment, body } => {
+                for clause in [initializer, condition, increment].into_iter().flatten() {
+                    self.visit(clause);
+                }
+                self.visit_scoped(body, &[]);
+            }
+            ASTNode::UnaryExpression { operand, .. } => self.visit(operand),
+            ASTNode::TypeCast { expression, .. } => self.visit(expression),
+            ASTNode::ConditionalExpression { condition, then_expr, else_expr } => {
+                self.visit(condition);
+                self.visit(then_expr);
+                self.visit(else_expr);
+            }
+            ASTNode::TryCatch { try_block, catch_clauses, finally_block } => {
+                self.visit_scoped(try_block, &[]);
+                for clause in catch_clauses.iter_mut() {
+                    let bindings: Vec<String> = clause.variable.iter().cloned().collect();
+                    self.visit_scoped(&mut clause.body, &bindings);
+                }
+                if let Some(finally_block) = finally_block {
+                    self.visit_scoped(finally_block, &[]);
+                }
+            }
+            ASTNode::ClassDeclaration { name, constructor, methods, properties, .. } => {
+                let name = name.clone();
+                self.declare(&name);
+                self.scopes.push(HashSet::new());
+                for property in properties.iter_mut() {
+                    self.visit(property);
+                }
+                if let Some(constructor) = constructor {
+                    self.visit(constructor);
+                }
+                for method in methods.iter_mut() {
+                    self.visit(method);
+                }
+                self.scopes.pop();
+            }
+            ASTNode::Constructor { params, body, .. } => {
+                self.visit_defaults(params);
+                let bindings: Vec<String> = params.iter().map(|param| param.name.clone()).collect();
+                self.visit_scoped(body, &bindings);
+            }
+            ASTNode::FieldDeclaration { name, value, .. } => {
+                if let Some(value) = value {
+                    self.visit(value);
+                }
+                let name = name.clone();
+                self.declare(&name);
+            }
+            ASTNode::MethodDeclaration { function, .. } => self.visit(function),
+            ASTNode::InterfaceDeclaration { name, methods, .. } => {
+                let name = name.clone();
+                self.declare(&name);
+                for method in methods.iter_mut() {
+                    self.visit(method);
+                }
+            }
+            ASTNode::EnumDeclaration { name, .. } |
+            ASTNode::RecordDeclaration { name, .. } => {
+                let name = name.clone();
+                self.declare(&name);
+            }
+            ASTNode::VariantPath { .. } |
+            ASTNode::TypeAlias { .. } |
+            ASTNode::FunctionSignature { .. } => {}
+            ASTNode::EveryLoop { interval, body } => {
+                self.visit(interval);
+                self.visit_scoped(body, &[]);
+            }
+            ASTNode::Block(statements) |
+            ASTNode::Spawn(statements) |
+            ASTNode::SeqBlock(statements) |
+            ASTNode::ParBlock(statements) => self.visit_scoped(statements, &[]),
+            ASTNode::AtBlock { offset, body } => {
+                self.visit(offset);
+                self.visit_scoped(body, &[]);
+            }
+            ASTNode::NoteEvent { note, duration } => {
+                self.visit(note);
+                if let Some(duration) = duration {
+                    self.visit(duration);
+                }
+            }
+            ASTNode::BlockExpression { statements, value } => {
+                self.scopes.push(HashSet::new());
+                for statement in statements.iter_mut() {
+                    self.visit(statement);
+                }
+                if let Some(value) = value {
+                    self.visit(value);
+                }
+                self.scopes.pop();
+            }
+            ASTNode::IfExpression { condition, then_branch, else_branch } => {
+                self.visit(condition);
+                self.visit(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.visit(else_branch);
+                }
+            }
+            ASTNode::MatchExpression { expression, cases } => {
+                self.visit(expression);
+                for case in cases.iter_mut() {
+                    let mut bindings = Vec::new();
+                    pattern_bindings(&case.pattern, &mut bindings);
+
+                    self.scopes.push(bindings.into_iter().collect());
+                    if let Some(guard) = &mut case.guard {
+                        self.visit(guard);
+                    }
+                    self.visit(&mut case.body);
+                    self.scopes.pop();
+                }
+            }
+        }
+    }
+}
+
+fn pattern_bindings(pattern: &Pattern, bindings: &mut Vec<String>) {
+    match pattern {
+        Pattern::Binding(name) |
+        Pattern::Rest(Some(name)) => bindings.push(name.clone()),
+        Pattern::Array(elements) => {
+            for element in elements {
+                pattern_bindings(element, bindings);
+            }
+        }
+        Pattern::Object(fields) => {
+            for (_, field) in fields {
+                pattern_bindings(field, bindings);
+            }
+        }
+        Pattern::Variant { fields, .. } => {
+            for field in fields {
+                pattern_bindings(field, bindings);
+            }
+        }
+        Pattern::Wildcard |
+        Pattern::Literal(_) |
+        Pattern::Range { .. } |
+        Pattern::Rest(None) => {}
+    }
+}
+
+// Binds every binary operator whose left operand is a class instance to one of
+// that class's operator methods (`operator +` or `/op_add/`), picked by the
+// type of the right operand; an Int operand may use a Float overload. Operators
+// whose operand types can't be worked out from declarations in this module are
+// left unresolved without an error.
+pub fn resolve_operators(program: &mut ASTNode) -> Result<(), Vec<String>> {
+    let mut classes = HashMap::new();
+    walk(program, &mut |node| {
+        if let ASTNode::ClassDeclaration { name, .. } = node {
+            classes.insert(name.clone(), ClassInfo::new(node));
+        }
+    });
+
+    let mut resolver = OperatorResolver {
+        classes,
+        scopes: vec![HashMap::new()],
+        errors: Vec::new(),
+    };
+    resolver.visit(program);
+
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+struct MethodInfo {
+    name: String,
+    is_static: bool,
+    params: Vec<TypeExpr>,
+    return_type: TypeExpr,
+}
+
+struct ClassInfo {
+    parent: Option<String>,
+    fields: Vec<(String, TypeExpr)>,
+    methods: Vec<MethodInfo>,
+}
+
+impl ClassInfo {
+    fn new(class: &ASTNode) -> Self {
+        let ASTNode::ClassDeclaration { extends, methods, properties, .. } = class else {
+            unreachable!();
+        };
+
+        let fields = properties.iter().filter_map(|property| match property {
+            ASTNode::FieldDeclaration { name, var_type, .. } |
+            ASTNode::ConstDeclaration { name, var_type, .. } => Some((name.clone(), var_type.clone())),
+            _ => None,
+        }).collect();
+        let methods = methods.iter().filter_map(|method| match method {
+            ASTNode::MethodDeclaration { is_static, function, .. } => match function.as_ref() {
+                ASTNode::FunctionDeclaration { name, params, return_type, .. } => Some(MethodInfo {
+                    name: name.clone(),
+                    is_static: *is_static,
+                    params: params.iter().map(|param| param.param_type.clone()).collect(),
+                    return_type: return_type.clone(),
+                }),
+                _ => None,
+            },
+            _ => None,
+        }).collect();
+
+        ClassInfo {
+            parent: extends.clone(),
+            fields,
+            methods,
+        }
+    }
+}
+
+struct OperatorResolver {
+    classes: HashMap<String, ClassInfo>,
+    scopes: Vec<HashMap<String, TypeExpr>>,
+    errors: Vec<String>,
+}
+
+impl OperatorResolver {
+    fn declare(&mut self, name: &str, var_type: &TypeExpr) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), var_type.clone());
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<TypeExpr> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name)).cloned()
+    }
+
+    fn visit_scoped(&mut self, nodes: &mut [ASTNode], bindings: &[(String, TypeExpr)]) {
+        self.scopes.push(bindings.iter().cloned().collect());
+        for node in nodes.iter_mut() {
+            self.visit(node);
+        }
+        self.scopes.pop();
+    }
+
+    fn visit(&mut self, node: &mut ASTNode) {
+        match node {
+            ASTNode::VariableDeclaration { var_type, name, value, .. } |
+            ASTNode::ConstDeclaration { var_type, name, value, .. } => {
+                self.visit(value);
+                self.declare(name, var_type);
+            }
+            ASTNode::FunctionDeclaration { params, body, .. } |
+            ASTNode::Lambda { params, body, .. } |
+            ASTNode::Constructor { params, body, .. } => {
+                for param in params.iter_mut() {
+                    if let Some(default) = &mut param.default {
+                        self.visit(default);
+                    }
+                }
+                let bindings: Vec<(String, TypeExpr)> = params.iter().map(|param| {
+                    // inside the body a variadic parameter holds an array
+                    let binding_type = if param.variadic {
+                        TypeExpr::Array(Box::new(param.param_type.clone()))
+                    } else {
+                        param.param_type.clone()
+                    };
+                    (param.name.clone(), binding_type)
+                }).collect();
+                self.visit_scoped(body, &bindings);
+            }
+            ASTNode::ClassDeclaration { name, .. } => {
+                // `this` and the fields are in scope, unqualified, inside the class body
+                let mut scope: HashMap<String, TypeExpr> = self.members(name).fields.into_iter().collect();
+                scope.insert(String::from("this"), TypeExpr::Named(name.clone()));
+                self.scopes.push(scope);
+                for child in children_mut(node) {
+                    self.visit(child);
+                }
+                self.scopes.pop();
+            }
+            ASTNode::Block(statements) => self.visit_scoped(statements, &[]),
+            ASTNode::BlockExpression { .. } => {
+                self.scopes.push(HashMap::new());
+                for child in children_mut(node) {
+                    self.visit(child);
+                }
+                self.scopes.pop();
+            }
+            ASTNode::BinaryExpression { left, operator, right, overload } => {
+                self.visit(left);
+                self.visit(right);
+                *overload = self.resolve(left, operator, right);
+            }
+            _ => {
+                for child in children_mut(node) {
+                    self.visit(child);
+                }
+            }
+        }
+    }
+
+    // Fields and methods of `class_name` and its ancestors; a subclass method
+    // hides an inherited one with the same name and parameter types.
+    fn members(&self, class_name: &str) -> Members<'_> {
+        let mut members = Members {
+            fields: Vec::new(),
+            methods: Vec::new(),
+        };
+        let mut visited = HashSet::new();
+        let mut current = Some(class_name);
+
+        while let Some(name) = current {
+            let Some(class) = self.classes.get(name) else {
+                break;
+            };
+            if !visited.insert(name) {
+                break;
+            }
+
+            for field in &class.fields {
+                if !members.fields.iter().any(|(existing, _)| *existing == field.0) {
+                    members.fields.push(field.clone());
+                }
+            }
+            // a subclass method hides the parent's; two in one class stay ambiguous
+            let overriding = members.methods.len();
+            for method in &class.methods {
+                if !members.methods[..overriding].iter().any(|existing| existing.name == method.name && existing.params == method.params) {
+                    members.methods.push(method);
+                }
+            }
+            current = class.parent.as_deref();
+        }
+        members
+    }
+
+    fn resolve(&mut self, left: &ASTNode, operator: &str, right: &ASTNode) -> Option<Overload> {
+        let method = operator_method(operator)?;
+        let class_name = match self.type_of(left)? {
+            TypeExpr::Named(name) | TypeExpr::Generic { name, .. } => name,
+            _ => return None,
+        };
+        if !self.classes.contains_key(&class_name) {
+            return None;
+        }
+
+        let members = self.members(&class_name);
+        let candidates: Vec<&MethodInfo> = members.methods.iter()
+            .copied()
+            .filter(|candidate| candidate.name == method && !candidate.is_static && candidate.params.len() == 1)
+            .collect();
+        // without an overload, == and != keep comparing instances by identity
+        if candidates.is_empty() && matches!(operator, "==" | "!=") {
+            return None;
+        }
+
+        let operand_type = self.type_of(right)?;
+        let accepts = |candidate: &&MethodInfo, wanted: &TypeExpr| candidate.params[0] == *wanted;
+        let mut matching: Vec<&MethodInfo> = candidates.iter().copied().filter(|candidate| accepts(candidate, &operand_type)).collect();
+        // an Int operand widens to Float when no overload takes Int itself
+        if matching.is_empty() && operand_type == TypeExpr::Named(String::from("Int")) {
+            let float = TypeExpr::Named(String::from("Float"));
+            matching = candidates.iter().copied().filter(|candidate| accepts(candidate, &float)).collect();
+        }
+        if let [chosen] = matching.as_slice() {
+            return Some(Overload {
+                class_name,
+                method: String::from(method),
+                operand_type: chosen.params[0].clone(),
+            });
+        }
+
+        let listed = candidates.iter()
+            .map(|candidate| format!("{}({})", candidate.name, candidate.params[0]))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let error = if candidates.is_empty() {
+            format!("Class '{}' does not overload operator '{}' (no method '{}' taking one argument)", class_name, operator, method)
+        } else if matching.is_empty() {
+            format!("No overload of operator '{}' on '{}' takes '{}'; candidates: {}", operator, class_name, operand_type, listed)
+        } else {
+            format!("Ambiguous use of operator '{}' on '{}'; candidates: {}", operator, class_name, listed)
+        };
+        self.errors.push(error);
+        None
+    }
+
+    // Best-effort static type of an expression, from declarations only.
+    fn type_of(&self, node: &ASTNode) -> Option<TypeExpr> {
+        match node {
+            ASTNode::Literal(value) if value.starts_with('"') => Some(TypeExpr::Named(String::from("String"))),
+            ASTNode::Literal(value) if value.contains('.') => Some(TypeExpr::Named(String::from("Float"))),
+            ASTNode::Literal(_) => Some(TypeExpr::Named(String::from("Int"))),
+            ASTNode::ChordLiteral(_) |
+            ASTNode::ChordSymbol { .. } => Some(TypeExpr::Named(String::from("Chord"))),
+            ASTNode::ScaleLiteral { .. } => Some(TypeExpr::Named(String::from("Scale"))),
+            ASTNode::Identifier(name) => self.lookup(name),
+            ASTNode::RecordLiteral { type_name, .. } => Some(TypeExpr::Named(type_name.clone())),
+            ASTNode::TypeCast { target_type, .. } => Some(target_type.clone()),
+            ASTNode::FunctionCall { callee, .. } => match callee.as_ref() {
+                ASTNode::Identifier(name) if self.classes.contains_key(name) => Some(TypeExpr::Named(name.clone())),
+                _ => None,
+            },
+            ASTNode::PropertyAccess { object, property } => {
+                let class_name = self.class_of(object)?;
+                let members = self.members(&class_name);
+                members.fields.into_iter().find(|(name, _)| name == property).map(|(_, field_type)| field_type)
+            }
+            ASTNode::MethodCall { object, method, .. } => {
+                let class_name = self.class_of(object)?;
+                self.return_type(&class_name, method)
+            }
+            ASTNode::BinaryExpression { overload: Some(overload), .. } => {
+                self.members(&overload.class_name).methods.into_iter()
+                    .find(|candidate| candidate.name == overload.method && candidate.params == [overload.operand_type.clone()])
+                    .map(|candidate| candidate.return_type.clone())
+            }
+            ASTNode::ConditionalExpression { then_expr, .. } => self.type_of(then_expr),
+            _ => None,
+        }
+    }
+
+    // The class an expression refers to or evaluates to: `Voice` in
+    // `Voice.create()`, or the declared class of `v` in `v.tune()`.
+    fn class_of(&self, node: &ASTNode) -> Option<String> {
+        if let ASTNode::Identifier(name) = node &&
+            self.lookup(name).is_none() &&
+            self.classes.contains_key(name) {
+            return Some(name.clone());
+        }
+        match self.type_of(node)? {
+            TypeExpr::Named(name) | TypeExpr::Generic { name, .. } if self.classes.contains_key(&name) => Some(name),
+            _ => None,
+        }
+    }
+
+    fn return_type(&self, class_name: &str, method: &str) -> Option<TypeExpr> {
+        let members = self.members(class_name);
+        let mut overloads = members.methods.iter().filter(|candidate| candidate.name == method);
+        match (overloads.next(), overloads.next()) {
+            (Some(only), None) => Some(only.return_type.clone()),
+            _ => None,
+        }
+    }
+}
+
+struct Members<'a> {
+    fields: Vec<(String, TypeExpr)>,
+    methods: Vec<&'a MethodInfo>,
+}
+
+// Constants are folded at compile time, so their initializers may only use
+// literals, operators and constants declared before them.
+fn check_constants(program: &ASTNode, imports: &[&ASTNode], errors: &mut Vec<String>) {
+    let mut constants: HashSet<&str> = HashSet::new();
+    for module in imports {
+        walk(module, &mut |node| {
+            if let ASTNode::ConstDeclaration { name, .. } = node {
+                constants.insert(name);
+            }
+        });
+    }
+    walk(program, &mut |node| {
+        let ASTNode::ConstDeclaration { name, value, .. } = node else {
+            return;
+        };
+        if !is_constant_expression(value, &constants) {
+            errors.push(format!("Initializer of constant '{}' is not a compile-time constant", name));
+        }
+        constants.insert(name);
+    });
+}
+
+fn is_constant_expression(node: &ASTNode, constants: &HashSet<&str>) -> bool {
+    match node {
+        ASTNode::Literal(_) |
+        ASTNode::Quantity { .. } |
+        ASTNode::ChordSymbol { .. } |
+        ASTNode::ScaleLiteral { .. } |
+        ASTNode::VariantPath { .. } => true,
+        ASTNode::Identifier(name) => constants.contains(name.as_str()),
+        ASTNode::UnaryExpression { operand, .. } => is_constant_expression(operand, constants),
+        ASTNode::TypeCast { expression, .. } => is_constant_expression(expression, constants),
+        ASTNode::BinaryExpression { left, right, .. } => {
+            is_constant_expression(left, constants) && is_constant_expression(right, constants)
+        }
+        ASTNode::ConditionalExpression { condition, then_expr, else_expr } => {
+            is_constant_expression(condition, constants) &&
+                is_constant_expression(then_expr, constants) &&
+                is_constant_expression(else_expr, constants)
+        }
+        ASTNode::ArrayLiteral(elements) => elements.iter().all(|element| is_constant_expression(element, constants)),
+        ASTNode::ObjectLiteral(properties) |
+        ASTNode::RecordLiteral { fields: properties, .. } => {
+            properties.iter().all(|(_, value)| is_constant_expression(value, constants))
+        }
+        ASTNode::FunctionCall { callee, arguments, .. } => {
+            matches!(**callee, ASTNode::VariantPath { .. }) &&
+                arguments.iter().all(|argument| is_constant_expression(argument, constants))
+        }
+        _ => false,
+    }
+}
+
+// Calls `visit` on `node` and every node below it, parents first.
+pub(crate) fn walk<'a>(node: &'a ASTNode, visit: &mut dyn FnMut(&'a ASTNode)) {
+    visit(node);
+    for child in children(node) {
+        walk(child, visit);
+    }
+}
+
+fn children(node: &ASTNode) -> Vec<&ASTNode> {
+    match node {
+        ASTNode::Program(nodes) |
+        ASTNode::ArrayLiteral(nodes) |
+        ASTNode::ChordLiteral(nodes) |
+        ASTNode::Block(nodes) |
+        ASTNode::Spawn(nodes) |
+        ASTNode::SeqBlock(nodes) |
+        ASTNode::ParBlock(nodes) => nodes.iter().collect(),
+        ASTNode::EveryLoop { interval, body } => std::iter::once(interval.as_ref()).chain(body.iter()).collect(),
+        ASTNode::AtBlock { offset, body } => std::iter::once(offset.as_ref()).chain(body.iter()).collect(),
+        ASTNode::NoteEvent { note, duration } => std::iter::once(note.as_ref()).chain(duration.as_deref()).collect(),
+        ASTNode::UseStatement { .. } |
+        ASTNode::MeterDeclaration { .. } |
+        ASTNode::KeyDeclaration { .. } |
+        ASTNode::Quantity { .. } |
+        ASTNode::ChordSymbol { .. } |
+        ASTNode::ScaleLiteral { .. } |
+        ASTNode::Literal(_) |
+        ASTNode::Identifier(_) |
+        ASTNode::FunctionReference(_) |
+        ASTNode::VariantPath { .. } |
+        ASTNode::TypeAlias { .. } |
+        ASTNode::EnumDeclaration { .. } |
+        ASTNode::RecordDeclaration { .. } => Vec::new(),
+        ASTNode::ReturnStatement(value) => value.as_deref().into_iter().collect(),
+        ASTNode::TempoDeclaration(value) |
+        ASTNode::ThrowStatement(value) |
+        ASTNode::WaitStatement(value) |
+        ASTNode::Await(value) |
+        ASTNode::NamedArgument { value, .. } |
+        ASTNode::ExpressionStatement(value) |
+        ASTNode::VariableDeclaration { value, .. } |
+        ASTNode::ConstDeclaration { value, .. } |
+        ASTNode::PropertyAccess { object: value, .. } |
+        ASTNode::UnaryExpression { operand: value, .. } |
+        ASTNode::TypeCast { expression: value, .. } |
+        ASTNode::MethodDeclaration { function: value, .. } => vec![value],
+        ASTNode::FunctionDeclaration { params, body, .. } |
+        ASTNode::Lambda { params, body, .. } |
+        ASTNode::Constructor { params, body, .. } => {
+            params.iter()
+                .filter_map(|param| param.default.as_deref())
+                .chain(body.iter())
+                .collect()
+        }
+        ASTNode::FunctionSignature { params, .. } => params.iter().filter_map(|param| param.default.as_deref()).collect(),
+        ASTNode::ObjectLiteral(properties) |
+        ASTNode::RecordLiteral { fields: properties, .. } => properties.iter().map(|(_, value)| value).collect(),
+        ASTNode::FunctionCall { callee, arguments, .. } => {
+            std::iter::once(callee.as_ref()).chain(arguments.iter()).collect()
+        }
+        ASTNode::MethodCall { object, arguments, .. } => {
+            std::iter::once(object.as_ref()).chain(arguments.iter()).collect()
+        }
+        ASTNode::BinaryExpression { left, right, .. } => vec![left, right],
+        ASTNode::IndexExpression { object, index } => vec![object, index],
+        ASTNode::SliceExpression { object, start, end } => {
+            std::iter::once(object.as_ref())
+                .chain(start.as_deref())
+                .chain(end.as_deref())
+                .collect()
+        }
+        ASTNode::Assignment { target, value } => vec![target, value],
+        ASTNode::WithContext { settings, body } => settings.iter().chain(body.iter()).collect(),
+        ASTNode::IfStatement { condition, then_branch, else_branch } => {
+            std::iter::once(condition.as_ref())
+                .chain(then_branch.iter())
+                .chain(else_branch.iter().flatten())
+                .collect()
+        }
+        ASTNode::WhileLoop { condition, body } => {
+            std::iter::once(condition.as_ref()).chain(body.iter()).collect()
+        }
+        ASTNode::ForLoop { initializer, condition, increment, body } => {
+            [initializer, condition, increment].into_iter()
+                .flatten()
+                .map(|clause| clause.as_ref())
+                .chain(body.iter())
+                .collect()
+        }
+        ASTNode::ConditionalExpression { condition, then_expr, else_expr } => vec![condition, then_expr, else_expr],
+        ASTNode::TryCatch { try_block, catch_clauses, finally_block } => {
+            try_block.iter()
+                .chain(catch_clauses.iter().flat_map(|clause| clause.body.iter()))
+                .chain(finally_block.iter().flatten())
+                .collect()
+        }
+        ASTNode::ClassDeclaration { constructor, methods, properties, .. } => {
+            properties.iter()
+                .chain(constructor.as_deref())
+                .chain(methods.iter())
+                .collect()
+        }
+        ASTNode::FieldDeclaration { value, .. } => value.as_deref().into_iter().collect(),
+        ASTNode::InterfaceDeclaration { methods, .. } => methods.iter().collect(),
+        ASTNode::BlockExpression { statements, value } => {
+            statements.iter().chain(value.as_deref()).collect()
+        }
+        ASTNode::IfExpression { condition, then_branch, else_branch } => {
+            [condition, then_branch].into_iter()
+                .map(|branch| branch.as_ref())
+                .chain(else_branch.as_deref())
+                .collect()
+        }
+        ASTNode::MatchExpression { expression, cases } => {
+            std::iter::once(expression.as_ref())
+                .chain(cases.iter().flat_map(|case| case.guard.as_deref().into_iter().chain(std::iter::once(case.body.as_ref()))))
+                .collect()
+        }
+    }
+}
+
+fn children_mut(node: &mut ASTNode) -> Vec<&mut ASTNode> {
+    match node {
+        ASTNode::Program(nodes) |
+        ASTNode::ArrayLiteral(nodes) |
+        ASTNode::ChordLiteral(nodes) |
+        ASTNode::Block(nodes) |
+        ASTNode::Spawn(nodes) |
+        ASTNode::SeqBlock(nodes) |
+        ASTNode::ParBlock(nodes) => nodes.iter_mut().collect(),
+        ASTNode::EveryLoop { interval, body } => std::iter::once(interval.as_mut()).chain(body.iter_mut()).collect(),
+        ASTNode::AtBlock { offset, body } => std::iter::once(offset.as_mut()).chain(body.iter_mut()).collect(),
+        ASTNode::NoteEvent { note, duration } => std::iter::once(note.as_mut()).chain(duration.as_deref_mut()).collect(),
+        ASTNode::UseStatement { .. } |
+        ASTNode::MeterDeclaration { .. } |
+        ASTNode::KeyDeclaration { .. } |
+        ASTNode::Quantity { .. } |
+        ASTNode::ChordSymbol { .. } |
+        ASTNode::ScaleLiteral { .. } |
+        ASTNode::Literal(_) |
+        ASTNode::Identifier(_) |
+        ASTNode::FunctionReference(_) |
+        ASTNode::VariantPath { .. } |
+        ASTNode::TypeAlias { .. } |
+        ASTNode::EnumDeclaration { .. } |
+        ASTNode::RecordDeclaration { .. } => Vec::new(),
+        ASTNode::ReturnStatement(value) => value.as_deref_mut().into_iter().collect(),
+        ASTNode::TempoDeclaration(value) |
+        ASTNode::ThrowStatement(value) |
+        ASTNode::WaitStatement(value) |
+        ASTNode::Await(value) |
+        ASTNode::NamedArgument { value, .. } |
+        ASTNode::ExpressionStatement(value) |
+        ASTNode::VariableDeclaration { value, .. } |
+        ASTNode::ConstDeclaration { value, .. } |
+        ASTNode::PropertyAccess { object: value, .. } |
+        ASTNode::UnaryExpression { operand: value, .. } |
+        ASTNode::TypeCast { expression: value, .. } |
+        ASTNode::MethodDeclaration { function: value, .. } => vec![value],
+        ASTNode::FunctionDeclaration { params, body, .. } |
+        ASTNode::Lambda { params, body, .. } |
+        ASTNode::Constructor { params, body, .. } => {
+            params.iter_mut()
+                .filter_map(|param| param.default.as_deref_mut())
+                .chain(body.iter_mut())
+                .collect()
+        }
+        ASTNode::FunctionSignature { params, .. } => params.iter_mut().filter_map(|param| param.default.as_deref_mut()).collect(),
+        ASTNode::ObjectLiteral(properties) |
+        ASTNode::RecordLiteral { fields: properties, .. } => properties.iter_mut().map(|(_, value)| value).collect(),
+        ASTNode::FunctionCall { callee, arguments, .. } => {
+            std::iter::once(callee.as_mut()).chain(arguments.iter_mut()).collect()
+        }
+        ASTNode::MethodCall { object, arguments, .. } => {
+            std::iter::once(object.as_mut()).chain(arguments.iter_mut()).collect()
+        }
+        ASTNode::BinaryExpression { left, right, .. } => vec![left, right],
+        ASTNode::IndexExpression { object, index } => vec![object, index],
+        ASTNode::SliceExpression { object, start, end } => {
+            std::iter::once(object.as_mut())
+                .chain(start.as_deref_mut())
+                .chain(end.as_deref_mut())
+                .collect()
+        }
+        ASTNode::Assignment { target, value } => vec![target, value],
+        ASTNode::WithContext { settings, body } => settings.iter_mut().chain(body.iter_mut()).collect(),
+        ASTNode::IfStatement { condition, then_branch, else_branch } => {
+            std::iter::once(condition.as_mut())
+                .chain(then_branch.iter_mut())
+                .chain(else_branch.iter_mut().flatten())
+                .collect()
+        }
+        ASTNode::WhileLoop { condition, body } => {
+            std::iter::once(condition.as_mut()).chain(body.iter_mut()).collect()
+        }
+        ASTNode::ForLoop { initializer, condition, increment, body } => {
+            [initializer, condition, increment].into_iter()
+                .flatten()
+                .map(|clause| clause.as_mut())
+                .chain(body.iter_mut())
+                .collect()
+        }
+        ASTNode::ConditionalExpression { condition, then_expr, else_expr } => vec![condition, then_expr, else_expr],
+        ASTNode::TryCatch { try_block, catch_clauses, finally_block } => {
+            try_block.iter_mut()
+                .chain(catch_clauses.iter_mut().flat_map(|clause| clause.body.iter_mut()))
+                .chain(finally_block.iter_mut().flatten())
+                .collect()
+        }
+        ASTNode::ClassDeclaration { constructor, methods, properties, .. } => {
+            properties.iter_mut()
+                .chain(constructor.as_deref_mut())
+                .chain(methods.iter_mut())
+                .collect()
+        }
+        ASTNode::FieldDeclaration { value, .. } => value.as_deref_mut().into_iter().collect(),
+        ASTNode::InterfaceDeclaration { methods, .. } => methods.iter_mut().collect(),
+        ASTNode::BlockExpression { statements, value } => {
+            statements.iter_mut().chain(value.as_deref_mut()).collect()
+        }
+        ASTNode::IfExpression { condition, then_branch, else_branch } => {
+            [condition, then_branch].into_iter()
+                .map(|branch| branch.as_mut())
+                .chain(else_branch.as_deref_mut())
+                .collect()
+        }
+        ASTNode::MatchExpression { expression, cases } => {
+            std::iter::once(expression.as_mut())
+                .chain(cases.iter_mut().flat_map(|case| case.guard.as_deref_mut().into_iter().chain(std::iter::once(case.body.as_mut()))))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use VYL::lexer::Lexer;
+use VYL::parser::Parser;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Parses every `tests/*.vyl` sample and compares its AST with the `.ast` file
+// next to it. Run with `VYL_BLESS=1` to rewrite the expected files.
+#[test]
+fn sample_programs_match_golden_asts() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let bless = env::var_os("VYL_BLESS").is_some();
+    let mut samples: Vec<_> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "vyl"))
+        .collect();
+    samples.sort();
+    assert!(!samples.is_empty(), "no sample programs in '{}'", directory.display());
+
+    let mut mismatched = Vec::new();
+    for sample in &samples {
+        let source = fs::read_to_string(sample).unwrap();
+        let ast = Parser::new(Lexer::new(&source).tokenize())
+            .parse()
+            .unwrap_or_else(|e| panic!("'{}' failed to parse: {}", sample.display(), e));
+        let actual = format!("{:#?}\n", ast);
+
+        let golden = sample.with_extension("ast");
+        if bless {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden)
+            .unwrap_or_else(|e| panic!("Cannot read '{}' ({}); run with VYL_BLESS=1 to create it", golden.display(), e));
+        if actual != expected {
+            mismatched.push(sample.display().to_string());
+        }
+    }
+
+    assert!(mismatched.is_empty(), "AST differs from the golden file for: {}", mismatched.join(", "));
+}
@@ -0,0 +1,141 @@
+use VYL::module::{ModuleGraph, ModuleLoader};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// A fresh directory under the system temp dir, populated with `files`.
+fn project(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("vyl-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&root);
+    for (path, source) in files {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, source).unwrap();
+    }
+    root
+}
+
+fn load(entry: &Path) -> Result<ModuleGraph, String> {
+    ModuleLoader::new(Vec::new(), entry.parent().unwrap().join("lib")).load(entry)
+}
+
+fn names(graph: &ModuleGraph) -> Vec<&str> {
+    graph.load_order().iter().map(|id| graph.get(*id).name.as_str()).collect()
+}
+
+#[test]
+fn file_module_is_preferred_over_package() {
+    let root = project("file-or-package", &[
+        ("main.vyl", "use [theory]; use [chords];"),
+        ("theory.vyl", "Int/file/ = 1;"),
+        ("theory/mod.vyl", "Int/package/ = 1;"),
+        ("chords/mod.vyl", "Int/package/ = 1;"),
+    ]);
+    let graph = load(&root.join("main.vyl")).unwrap();
+
+    assert_eq!(graph.find("theory").unwrap().path, root.join("theory.vyl"));
+    assert_eq!(graph.find("chords").unwrap().path, root.join("chords/mod.vyl"));
+}
+
+#[test]
+fn nested_paths_resolve_to_directories() {
+    let root = project("nested", &[
+        ("main.vyl", "use [theory.scales.{major}];"),
+        ("theory/scales.vyl", "Int/major/ = 1;"),
+    ]);
+    let graph = load(&root.join("main.vyl")).unwrap();
+
+    assert_eq!(names(&graph), ["theory.scales", "main"]);
+}
+
+#[test]
+fn lib_directory_next_to_the_entry_is_searched_last() {
+    let root = project("lib-fallback", &[
+        ("main.vyl", "use [audio]; use [theory];"),
+        ("theory.vyl", "Int/local/ = 1;"),
+        ("lib/theory.vyl", "Int/library/ = 1;"),
+        ("lib/audio.vyl", "Int/library/ = 1;"),
+    ]);
+    let graph = ModuleGraph::load(&root.join("main.vyl")).unwrap();
+
+    assert_eq!(graph.find("audio").unwrap().path, root.join("lib/audio.vyl"));
+    assert_eq!(graph.find("theory").unwrap().path, root.join("theory.vyl"));
+}
+
+#[test]
+fn vyl_path_directories_are_searched() {
+    let shared = project("vyl-path-shared", &[("tuning.vyl", "pub const Float/A4/ = 440.0;")]);
+    let root = project("vyl-path", &[("main.vyl", "use [tuning]; const Float/A5/ = A4 * 2;")]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_VYL"))
+        .arg(root.join("main.vyl"))
+        .env("VYL_PATH", &shared)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("AST (tuning):"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_VYL"))
+        .arg(root.join("main.vyl"))
+        .env_remove("VYL_PATH")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Cannot find module 'tuning'"));
+}
+
+#[test]
+fn shared_imports_are_loaded_once() {
+    let root = project("load-once", &[
+        ("main.vyl", "use [melody]; use [harmony]; use [theory];"),
+        ("melody.vyl", "use [theory];"),
+        ("harmony.vyl", "use [theory]; use [melody];"),
+        ("theory.vyl", "Int/x/ = 1;"),
+    ]);
+    let graph = load(&root.join("main.vyl")).unwrap();
+
+    assert_eq!(graph.modules().len(), 4);
+    assert_eq!(names(&graph), ["theory", "melody", "harmony", "main"]);
+    let theory = graph.load_order()[0];
+    assert!(graph.modules().iter().filter(|module| module.name != "theory").all(|module| module.imports.contains(&theory)));
+}
+
+#[test]
+fn import_cycles_report_the_chain() {
+    let root = project("cycle", &[
+        ("main.vyl", "use [a];"),
+        ("a.vyl", "use [b];"),
+        ("b.vyl", "use [a];"),
+    ]);
+    let error = load(&root.join("main.vyl")).unwrap_err();
+
+    assert_eq!(error, format!(
+        "Import cycle detected:\n     a ({})\n  -> b ({})\n  -> a ({})",
+        root.join("a.vyl").display(),
+        root.join("b.vyl").display(),
+        root.join("a.vyl").display(),
+    ));
+}
+
+#[test]
+fn missing_modules_list_the_searched_directories() {
+    let root = project("missing", &[("main.vyl", "use [nowhere];")]);
+    let error = load(&root.join("main.vyl")).unwrap_err();
+
+    assert_eq!(error, format!(
+        "Cannot find module 'nowhere' imported from '{}' (searched '{}', '{}')",
+        root.join("main.vyl").display(),
+        root.display(),
+        root.join("lib").display(),
+    ));
+}
+
+#[test]
+fn sample_programs_run() {
+    for sample in ["sample.vyl", "composition.vyl"] {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join(sample);
+        let output = Command::new(env!("CARGO_BIN_EXE_VYL")).arg(&path).env_remove("VYL_PATH").output().unwrap();
+
+        assert!(output.status.success(), "{}: {}", sample, String::from_utf8_lossy(&output.stderr));
+    }
+}
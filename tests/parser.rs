@@ -0,0 +1,416 @@
+use VYL::ast::{ASTNode, Pattern, TypeExpr, Visibility};
+use VYL::lexer::Lexer;
+use VYL::parser::Parser;
+
+fn parse(source: &str) -> Vec<ASTNode> {
+    match Parser::new(Lexer::new(source).tokenize()).parse() {
+        Ok(ASTNode::Program(statements)) => statements,
+        Ok(other) => panic!("expected a program, got {:#?}", other),
+        Err(e) => panic!("failed to parse {:?}: {}", source, e),
+    }
+}
+
+fn parse_error(source: &str) -> String {
+    match Parser::new(Lexer::new(source).tokenize()).parse() {
+        Ok(ast) => panic!("expected {:?} to fail, got {:#?}", source, ast),
+        Err(e) => e,
+    }
+}
+
+// The initializer of `source`, a single variable declaration.
+fn initializer(source: &str) -> ASTNode {
+    match parse(source).pop() {
+        Some(ASTNode::VariableDeclaration { value, .. }) => *value,
+        other => panic!("expected a variable declaration, got {:#?}", other),
+    }
+}
+
+fn is_identifier(node: &ASTNode, expected: &str) -> bool {
+    matches!(node, ASTNode::Identifier(name) if name == expected)
+}
+
+fn is_literal(node: &ASTNode, expected: &str) -> bool {
+    matches!(node, ASTNode::Literal(value) if value == expected)
+}
+
+fn named(name: &str) -> TypeExpr {
+    TypeExpr::Named(String::from(name))
+}
+
+#[test]
+fn typed_lambdas_keep_their_return_type() {
+    let ASTNode::Lambda { return_type, .. } = initializer("Lambda/f/ = /(Note n)Note => n;") else {
+        panic!("expected a lambda");
+    };
+    assert_eq!(return_type, Some(named("Note")));
+
+    let ASTNode::Lambda { return_type, body, .. } = initializer("Lambda/g/ = /() { log(1); };") else {
+        panic!("expected a lambda");
+    };
+    assert_eq!(return_type, Some(named("Void")));
+    assert_eq!(body.len(), 1);
+}
+
+#[test]
+fn match_patterns() {
+    let source = "Int/r/ = match (x) {
+        0..=4 => { 1 }
+        -2..0 => { 2 }
+        [first, ..rest] => { 3 }
+        { root: r, voicing } => { 4 }
+        n if n > 3 => { 5 }
+        _ => { 6 }
+    };";
+    let ASTNode::MatchExpression { cases, .. } = initializer(source) else {
+        panic!("expected a match expression");
+    };
+    assert_eq!(cases.len(), 6);
+
+    assert!(matches!(&cases[0].pattern, Pattern::Range { start, end, inclusive: true } if start == "0" && end == "4"));
+    assert!(matches!(&cases[1].pattern, Pattern::Range { start, end, inclusive: false } if start == "-2" && end == "0"));
+    let Pattern::Array(elements) = &cases[2].pattern else {
+        panic!("expected an array pattern, got {:#?}", cases[2].pattern);
+    };
+    assert!(matches!(elements.as_slice(), [Pattern::Binding(first), Pattern::Rest(Some(rest))] if first == "first" && rest == "rest"));
+    let Pattern::Object(fields) = &cases[3].pattern else {
+        panic!("expected an object pattern, got {:#?}", cases[3].pattern);
+    };
+    assert_eq!(fields.len(), 2);
+    assert!(matches!(&cases[4].pattern, Pattern::Binding(name) if name == "n"));
+    assert!(cases[4].guard.is_some());
+    assert!(matches!(cases[5].pattern, Pattern::Wildcard));
+}
+
+#[test]
+fn pitch_chord_and_boolean_patterns_are_literals() {
+    let source = "Int/r/ = match (x) {
+        C4 => { 1 }
+        Cmaj7 => { 2 }
+        C4..=B4 => { 3 }
+        true => { 4 }
+        Shape::Circle(radius) => { 5 }
+    };";
+    let ASTNode::MatchExpression { cases, .. } = initializer(source) else {
+        panic!("expected a match expression");
+    };
+
+    assert!(matches!(&cases[0].pattern, Pattern::Literal(value) if value == "C4"));
+    assert!(matches!(&cases[1].pattern, Pattern::Literal(value) if value == "Cmaj7"));
+    assert!(matches!(&cases[2].pattern, Pattern::Range { start, end, inclusive: true } if start == "C4" && end == "B4"));
+    assert!(matches!(&cases[3].pattern, Pattern::Literal(value) if value == "true"));
+    assert!(matches!(&cases[4].pattern, Pattern::Variant { enum_name, variant, fields } if enum_name == "Shape" && variant == "Circle" && fields.len() == 1));
+}
+
+#[test]
+fn division_inside_blocks_is_an_expression() {
+    let ASTNode::BlockExpression { value: Some(value), .. } = initializer("Int/y/ = { x / 2 };") else {
+        panic!("expected a block with a value");
+    };
+    assert!(matches!(*value, ASTNode::BinaryExpression { ref operator, .. } if operator == "/"));
+
+    let ASTNode::BlockExpression { value: Some(value), .. } = initializer("Float/f/ = { Float/(x) };") else {
+        panic!("expected a block with a value");
+    };
+    assert!(matches!(*value, ASTNode::TypeCast { .. }));
+
+    assert!(matches!(initializer("Int/z/ = if (a) { x / 2 } else { 3 };"), ASTNode::IfExpression { .. }));
+    assert!(matches!(parse("x / 2;").as_slice(), [ASTNode::ExpressionStatement(_)]));
+}
+
+#[test]
+fn match_statements_are_expressions() {
+    let statements = parse("match (x) { 0 => f(), _ => g() };
+        match (x) { 0 => f(), _ => g() }
+        match (x) { _ => voicing }.invert();");
+
+    assert!(matches!(statements.as_slice(), [
+        ASTNode::ExpressionStatement(first),
+        ASTNode::ExpressionStatement(second),
+        ASTNode::ExpressionStatement(third),
+    ] if matches!(**first, ASTNode::MatchExpression { .. }) &&
+        matches!(**second, ASTNode::MatchExpression { .. }) &&
+        matches!(**third, ASTNode::MethodCall { .. })));
+}
+
+#[test]
+fn generic_functions_and_type_arguments() {
+    let statements = parse("/map/<T, U>(List<T> xs, (T) -> U f)List<U> { return xs.map<U>(f); }");
+    let [ASTNode::FunctionDeclaration { type_params, params, return_type, .. }] = statements.as_slice() else {
+        panic!("expected a function, got {:#?}", statements);
+    };
+    assert_eq!(type_params.len(), 2);
+    assert_eq!(params[0].param_type, TypeExpr::Generic { name: String::from("List"), arguments: vec![named("T")] });
+    assert_eq!(params[1].param_type, TypeExpr::Function { params: vec![named("T")], return_type: Box::new(named("U")) });
+    assert_eq!(return_type.to_string(), "List<U>");
+
+    let ASTNode::FunctionCall { type_arguments, arguments, .. } = initializer("List<Chord>/cs/ = map<Note, Chord>(notes, toChord);") else {
+        panic!("expected a call with type arguments");
+    };
+    assert_eq!(type_arguments, vec![named("Note"), named("Chord")]);
+    assert_eq!(arguments.len(), 2);
+}
+
+#[test]
+fn less_than_is_still_a_comparison() {
+    let ASTNode::BinaryExpression { operator, .. } = initializer("Bool/b/ = a < b;") else {
+        panic!("expected a comparison");
+    };
+    assert_eq!(operator, "<");
+}
+
+#[test]
+fn records_and_record_literals() {
+    let statements = parse("record /Event/ { Note pitch; Duration dur; }
+        Event/e/ = Event{ pitch: C4, dur: 1/4 };");
+    let [ASTNode::RecordDeclaration { name, fields, .. }, ASTNode::VariableDeclaration { value, .. }] = statements.as_slice() else {
+        panic!("expected a record and a variable, got {:#?}", statements);
+    };
+    assert_eq!(name, "Event");
+    assert_eq!(fields, &vec![(String::from("pitch"), named("Note")), (String::from("dur"), named("Duration"))]);
+
+    let ASTNode::RecordLiteral { type_name, fields } = value.as_ref() else {
+        panic!("expected a record literal, got {:#?}", value);
+    };
+    assert_eq!(type_name, "Event");
+    assert_eq!(fields.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), ["pitch", "dur"]);
+}
+
+#[test]
+fn uppercase_constant_before_a_block_is_not_a_record() {
+    let statements = parse("with tempo BASE { wait 1; }");
+    let [ASTNode::WithContext { settings, body }] = statements.as_slice() else {
+        panic!("expected a with block, got {:#?}", statements);
+    };
+    assert!(matches!(settings.as_slice(), [ASTNode::TempoDeclaration(value)] if matches!(**value, ASTNode::Identifier(ref name) if name == "BASE")));
+    assert_eq!(body.len(), 1);
+
+    assert!(matches!(initializer("Event/e/ = Event{ };"), ASTNode::RecordLiteral { .. }));
+}
+
+#[test]
+fn const_visibility() {
+    let statements = parse("const Int/A/ = 1; pub const Int/B/ = 2;");
+    assert!(matches!(statements.as_slice(), [
+        ASTNode::ConstDeclaration { visibility: Visibility::Private, .. },
+        ASTNode::ConstDeclaration { visibility: Visibility::Public, .. },
+    ]));
+}
+
+#[test]
+fn use_statements() {
+    let statements = parse("use [theory.scales.{major, minor as min}];
+        pub use [theory.chords as ch, audio];");
+    let [ASTNode::UseStatement { visibility: Visibility::Private, imports: first }, ASTNode::UseStatement { visibility: Visibility::Public, imports: second }] = statements.as_slice() else {
+        panic!("expected two use statements, got {:#?}", statements);
+    };
+
+    assert_eq!(first[0].path, ["theory", "scales"]);
+    assert_eq!(first[0].items.len(), 2);
+    assert_eq!(first[0].items[1].alias.as_deref(), Some("min"));
+    assert_eq!(second.len(), 2);
+    assert_eq!(second[0].alias.as_deref(), Some("ch"));
+    assert_eq!(second[1].path, ["audio"]);
+}
+
+#[test]
+fn tempo_takes_the_same_operands_alone_and_in_with() {
+    let statements = parse("tempo BASE_TEMPO; tempo 120bpm; with tempo BASE_TEMPO * 2, key F# minor { } tempo = 3;");
+    assert!(matches!(statements.as_slice(), [
+        ASTNode::TempoDeclaration(_),
+        ASTNode::TempoDeclaration(_),
+        ASTNode::WithContext { .. },
+        ASTNode::ExpressionStatement(assignment),
+    ] if matches!(**assignment, ASTNode::Assignment { .. })));
+
+    let ASTNode::WithContext { settings, .. } = &statements[2] else {
+        unreachable!();
+    };
+    assert!(matches!(&settings[1], ASTNode::KeyDeclaration { tonic, mode } if tonic == "F#" && mode == "minor"));
+
+    let error = parse_error("with tempo (base) { }");
+    assert!(error.contains("Expected tempo after 'tempo'"), "{}", error);
+}
+
+#[test]
+fn chords_and_scales() {
+    let ASTNode::ChordLiteral(notes) = initializer("Chord/c/ = <C#4 F#4 A4>;") else {
+        panic!("expected a chord literal");
+    };
+    assert_eq!(notes.len(), 3);
+    assert!(is_identifier(&notes[0], "C#4"));
+
+    let ASTNode::ChordSymbol { root, quality } = initializer("Chord/d/ = F#m7;") else {
+        panic!("expected a chord symbol");
+    };
+    assert_eq!((root.as_str(), quality.as_str()), ("F#", "m7"));
+
+    let ASTNode::ScaleLiteral { tonic, mode } = initializer("Scale/s/ = scale(D, dorian);") else {
+        panic!("expected a scale literal");
+    };
+    assert_eq!((tonic.as_str(), mode.as_str()), ("D", "dorian"));
+}
+
+#[test]
+fn pipelines_pass_the_left_side_as_first_argument() {
+    let ASTNode::FunctionCall { callee, arguments, .. } = initializer("Sequence/a/ = motif |> transpose(5) |> retrograde();") else {
+        panic!("expected a call");
+    };
+    assert!(is_identifier(&callee, "retrograde"));
+    let [ASTNode::FunctionCall { callee, arguments, .. }] = arguments.as_slice() else {
+        panic!("expected a nested call, got {:#?}", arguments);
+    };
+    assert!(is_identifier(callee, "transpose"));
+    assert!(is_identifier(&arguments[0], "motif"));
+    assert!(is_literal(&arguments[1], "5"));
+}
+
+#[test]
+fn operator_overloads_become_methods() {
+    let statements = parse("class /Note/ {
+        operator +(Interval step)Note { return this; }
+        operator ==(Note other)Bool { return true; }
+    }");
+    let [ASTNode::ClassDeclaration { methods, .. }] = statements.as_slice() else {
+        panic!("expected a class, got {:#?}", statements);
+    };
+    let names: Vec<&str> = methods.iter().map(|method| match method {
+        ASTNode::MethodDeclaration { function, .. } => match function.as_ref() {
+            ASTNode::FunctionDeclaration { name, .. } => name.as_str(),
+            other => panic!("expected a function, got {:#?}", other),
+        },
+        other => panic!("expected a method, got {:#?}", other),
+    }).collect();
+    assert_eq!(names, ["op_add", "op_eq"]);
+
+    let error = parse_error("class /Note/ { operator +(Int a, Int b)Note { return this; } }");
+    assert!(error.contains("exactly one parameter"), "{}", error);
+}
+
+#[test]
+fn default_variadic_and_named_arguments() {
+    let statements = parse("/chord/(Int velocity = 90, Note... notes)Chord { return notes; }
+        Chord/c/ = chord(velocity: 80);");
+    let [ASTNode::FunctionDeclaration { params, .. }, ASTNode::VariableDeclaration { value, .. }] = statements.as_slice() else {
+        panic!("expected a function and a variable, got {:#?}", statements);
+    };
+    assert!(params[0].default.is_some() && !params[0].variadic);
+    assert!(params[1].default.is_none() && params[1].variadic);
+
+    let ASTNode::FunctionCall { arguments, .. } = value.as_ref() else {
+        panic!("expected a call, got {:#?}", value);
+    };
+    assert!(matches!(arguments.as_slice(), [ASTNode::NamedArgument { name, .. }] if name == "velocity"));
+
+    let error = parse_error("/f/(Note... notes, Int x)Void { }");
+    assert!(error.contains("last"), "{}", error);
+    let error = parse_error("Int/x/ = f(a: 1, 2);");
+    assert!(error.contains("named"), "{}", error);
+}
+
+#[test]
+fn arrow_lambda_leaves_return_type_unset() {
+    let ASTNode::Lambda { params, return_type, body, .. } = initializer("Lambda/f/ = /(Int x) => x * 2;") else {
+        panic!("expected a lambda");
+    };
+    assert_eq!(params.len(), 1);
+    assert_eq!(params[0].param_type, named("Int"));
+    assert_eq!(return_type, None);
+    assert!(matches!(body.as_slice(), [ASTNode::ReturnStatement(Some(_))]));
+}
+
+#[test]
+fn attributes_on_constants_and_fields() {
+    let statements = parse("@export const Float/A4/ = 440.0;
+        class /Synth/ { @range(0, 1) pub Float/gain/ = 0.5; }");
+    let [ASTNode::ConstDeclaration { attributes, .. }, ASTNode::ClassDeclaration { properties, .. }] = statements.as_slice() else {
+        panic!("expected a constant and a class, got {:#?}", statements);
+    };
+    assert_eq!(attributes[0].name, "export");
+    let [ASTNode::FieldDeclaration { attributes, .. }] = properties.as_slice() else {
+        panic!("expected a field, got {:#?}", properties);
+    };
+    assert_eq!(attributes[0].name, "range");
+    assert_eq!(attributes[0].arguments.len(), 2);
+
+    let error = parse_error("@inline use [audio];");
+    assert!(error.contains("Attributes can only be applied"), "{}", error);
+}
+
+#[test]
+fn attributed_declarations_inside_block_expressions() {
+    let ASTNode::IfExpression { then_branch, .. } = initializer("Int/y/ = if (x) { @inline Int/z/ = 2; z } else { 3 };") else {
+        panic!("expected an if expression");
+    };
+    let ASTNode::BlockExpression { statements, value: Some(_) } = *then_branch else {
+        panic!("expected a block with a value, got {:#?}", then_branch);
+    };
+    assert!(matches!(statements.as_slice(), [ASTNode::VariableDeclaration { attributes, .. }] if attributes[0].name == "inline"));
+}
+
+#[test]
+fn scheduling_statements_inside_block_expressions() {
+    let ASTNode::IfExpression { then_branch, .. } = initializer("Int/y/ = if (c) { wait 1/4; 2 } else { 3 };") else {
+        panic!("expected an if expression");
+    };
+    assert!(matches!(*then_branch, ASTNode::BlockExpression { ref statements, value: Some(_) } if matches!(statements[0], ASTNode::WaitStatement(_))));
+
+    let ASTNode::BlockExpression { statements, value } = initializer("Task/t/ = { every 1bar { tick(); } every LIMIT { tock(); } spawn { wait 1; } spawn { wait 2; } };") else {
+        panic!("expected a block expression");
+    };
+    assert!(matches!(statements.as_slice(), [
+        ASTNode::EveryLoop { .. },
+        ASTNode::EveryLoop { interval, .. },
+        ASTNode::ExpressionStatement(spawn),
+    ] if matches!(**interval, ASTNode::Identifier(ref name) if name == "LIMIT") && matches!(**spawn, ASTNode::Spawn(_))));
+    assert!(matches!(value.as_deref(), Some(ASTNode::Spawn(_))));
+}
+
+#[test]
+fn wait_and_every_take_the_same_operands() {
+    let statements = parse("/f/() { wait BEAT; every BEAT { wait 1/8; } wait(3); }");
+    let [ASTNode::FunctionDeclaration { body, .. }] = statements.as_slice() else {
+        panic!("expected a function, got {:#?}", statements);
+    };
+    assert!(matches!(body.as_slice(), [
+        ASTNode::WaitStatement(_),
+        ASTNode::EveryLoop { .. },
+        ASTNode::ExpressionStatement(call),
+    ] if matches!(**call, ASTNode::FunctionCall { .. })));
+}
+
+#[test]
+fn throw_and_typed_catch_clauses() {
+    let statements = parse("try { risky(); } catch (NoteError e) { throw e; } catch (_) { } finally { close(); }");
+    let [ASTNode::TryCatch { catch_clauses, finally_block, .. }] = statements.as_slice() else {
+        panic!("expected a try statement, got {:#?}", statements);
+    };
+    assert_eq!(catch_clauses.len(), 2);
+    assert_eq!(catch_clauses[0].error_type, Some(named("NoteError")));
+    assert_eq!(catch_clauses[0].variable.as_deref(), Some("e"));
+    assert!(matches!(catch_clauses[0].body.as_slice(), [ASTNode::ThrowStatement(_)]));
+    assert_eq!(catch_clauses[1].error_type, None);
+    assert!(finally_block.is_some());
+
+    let error = parse_error("try { risky(); } catch (_) { } catch (NoteError e) { }");
+    assert!(error.contains("last"), "{}", error);
+}
+
+#[test]
+fn throw_inside_match_arms_and_value_blocks() {
+    let ASTNode::MatchExpression { cases, .. } = initializer("Int/y/ = match (x) { 0 => { throw Err{ code: 1 }; }, _ => 1 };") else {
+        panic!("expected a match expression");
+    };
+    let ASTNode::BlockExpression { statements, value: None } = cases[0].body.as_ref() else {
+        panic!("expected a block, got {:#?}", cases[0].body);
+    };
+    assert!(matches!(statements.as_slice(), [ASTNode::ThrowStatement(error)] if matches!(**error, ASTNode::RecordLiteral { .. })));
+
+    let ASTNode::BlockExpression { statements, value: Some(_) } = initializer("Int/z/ = { if (bad) { throw Error(); } 2 };") else {
+        panic!("expected a block with a value");
+    };
+    let [ASTNode::ExpressionStatement(condition)] = statements.as_slice() else {
+        panic!("expected a single statement, got {:#?}", statements);
+    };
+    assert!(matches!(condition.as_ref(), ASTNode::IfExpression { then_branch, .. }
+        if matches!(then_branch.as_ref(), ASTNode::BlockExpression { statements, .. } if matches!(statements[0], ASTNode::ThrowStatement(_)))));
+}
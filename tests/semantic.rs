@@ -0,0 +1,303 @@
+use VYL::ast::{ASTNode, Overload, TypeExpr};
+use VYL::lexer::Lexer;
+use VYL::parser::Parser;
+use VYL::semantic;
+
+fn parse(source: &str) -> ASTNode {
+    Parser::new(Lexer::new(source).tokenize())
+        .parse()
+        .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", source, e))
+}
+
+// The lambda declared by the last statement of `node`, looking into
+// function bodies.
+fn declared_lambda(node: &ASTNode) -> &ASTNode {
+    match node {
+        ASTNode::Program(statements) |
+        ASTNode::FunctionDeclaration { body: statements, .. } => declared_lambda(statements.last().unwrap()),
+        ASTNode::VariableDeclaration { value, .. } => value,
+        other => panic!("no lambda in {:#?}", other),
+    }
+}
+
+// The lambda an arrow lambda returns.
+fn returned_lambda(lambda: &ASTNode) -> &ASTNode {
+    match lambda {
+        ASTNode::Lambda { body, .. } => match body.as_slice() {
+            [ASTNode::ReturnStatement(Some(value))] => value,
+            other => panic!("expected a single return, got {:#?}", other),
+        },
+        other => panic!("expected a lambda, got {:#?}", other),
+    }
+}
+
+fn captures(node: &ASTNode) -> &[String] {
+    match node {
+        ASTNode::Lambda { captures, .. } => captures,
+        other => panic!("expected a lambda, got {:#?}", other),
+    }
+}
+
+#[test]
+fn lambdas_capture_enclosing_locals() {
+    let mut program = parse("/f/(Int n) { Int/k/ = 1; Lambda/g/ = /(Int x) => x + n + k; }");
+    semantic::resolve_captures(&mut program);
+
+    assert_eq!(captures(declared_lambda(&program)), ["n", "k"]);
+}
+
+#[test]
+fn nested_lambdas_carry_captures_through() {
+    let mut program = parse("/f/(Int n) { Lambda/g/ = /(Int x) => /(Int y) => y + x + n; }");
+    semantic::resolve_captures(&mut program);
+
+    let outer = declared_lambda(&program);
+    assert_eq!(captures(outer), ["n"]);
+    assert_eq!(captures(returned_lambda(outer)), ["x", "n"]);
+}
+
+#[test]
+fn lambda_parameters_shadow_enclosing_names() {
+    let mut program = parse("/f/(Int n) { Lambda/g/ = /(Int n) => n * 2; }");
+    semantic::resolve_captures(&mut program);
+
+    assert!(captures(declared_lambda(&program)).is_empty());
+}
+
+#[test]
+fn globals_are_not_captured() {
+    let mut program = parse("Int/base/ = 60; Lambda/g/ = /(Int x) => x + base; /f/() { Lambda/h/ = /() => base; }");
+    semantic::resolve_captures(&mut program);
+
+    let ASTNode::Program(statements) = &program else {
+        unreachable!();
+    };
+    assert!(captures(declared_lambda(&statements[1])).is_empty());
+    assert!(captures(declared_lambda(&statements[2])).is_empty());
+}
+
+fn check(source: &str) -> Result<(), Vec<String>> {
+    semantic::check(&parse(source), &[])
+}
+
+#[test]
+fn boolean_patterns_are_not_catch_alls() {
+    let errors = check("/f/(Bool b)Int { match (b) { true => { return 1; } false => { return 0; } } }").unwrap_err();
+    assert_eq!(errors, ["Function 'f' returns 'Int' but not every path ends in a return"]);
+
+    assert!(check("/f/(Bool b)Int { match (b) { true => { return 1; } other => { return 0; } } }").is_ok());
+}
+
+#[test]
+fn record_literals_are_checked_against_the_declaration() {
+    let declaration = "record /Event/ { Note pitch; Float velocity; }";
+    assert!(check(&format!("{} Event/e/ = Event{{ pitch: C4, velocity: 0.8 }};", declaration)).is_ok());
+
+    let errors = check(&format!("{} Event/e/ = Event{{ pitch: C4, pitch: D4, volume: 1 }};", declaration)).unwrap_err();
+    assert_eq!(errors, [
+        "Field 'pitch' is given more than once in 'Event' literal",
+        "Record 'Event' has no field 'volume'",
+        "Missing field 'velocity' in 'Event' literal",
+    ]);
+
+    let errors = check("Note/n/ = Note{ pitch: 60 };").unwrap_err();
+    assert_eq!(errors, ["'Note' is not a record type"]);
+}
+
+#[test]
+fn imported_records_count_as_declared() {
+    let events = parse("record /Event/ { Note pitch; }");
+    let program = parse("Event/e/ = Event{ pitch: C4 };");
+
+    assert!(semantic::check(&program, &[]).is_err());
+    assert!(semantic::check(&program, &[&events]).is_ok());
+}
+
+#[test]
+fn constant_initializers_must_be_compile_time_constants() {
+    assert!(check("const Float/A4/ = 440.0; const Float/A5/ = A4 * 2; const Chord/HOME/ = Cmaj7;").is_ok());
+
+    let errors = check("Float/base/ = 440.0; const Float/A/ = base * 2; const Float/B/ = tune(A); const Float/C/ = D; const Float/D/ = 1.0;").unwrap_err();
+    assert_eq!(errors, [
+        "Initializer of constant 'A' is not a compile-time constant",
+        "Initializer of constant 'B' is not a compile-time constant",
+        "Initializer of constant 'C' is not a compile-time constant",
+    ]);
+}
+
+#[test]
+fn imported_constants_count_as_constant() {
+    let tuning = parse("pub const Float/A4_FREQ/ = 440.0;");
+    let program = parse("const Float/A5/ = A4_FREQ * 2;");
+
+    assert!(semantic::check(&program, &[]).is_err());
+    assert!(semantic::check(&program, &[&tuning]).is_ok());
+}
+
+const OPERATOR_CLASSES: &str = "
+    class /Interval/ { pub Int/semitones/; }
+    class /Note/ {
+        pub Int/pitch/;
+        operator +(Interval step)Note { return this; }
+        /op_add/(Int semitones)Note { return this; }
+    }
+    class /Gain/ { operator *(Float factor)Gain { return this; } }
+    class /Twice/ {
+        operator *(Int times)Twice { return this; }
+        /op_mul/(Int count)Twice { return this; }
+    }
+    Note/c/ = Note(60);
+    Gain/g/ = Gain();
+";
+
+// Resolves operators in `source` (after the operator classes) and returns the
+// overload picked for each top-level variable initializer that is a binary
+// expression.
+fn resolve_operators(source: &str) -> Result<Vec<Option<Overload>>, Vec<String>> {
+    let mut program = parse(&format!("{}{}", OPERATOR_CLASSES, source));
+    semantic::resolve_operators(&mut program)?;
+
+    let ASTNode::Program(statements) = program else {
+        unreachable!();
+    };
+    Ok(statements.into_iter().filter_map(|statement| match statement {
+        ASTNode::VariableDeclaration { value, .. } => match *value {
+            ASTNode::BinaryExpression { overload, .. } => Some(overload),
+            _ => None,
+        },
+        _ => None,
+    }).collect())
+}
+
+fn overload(class_name: &str, method: &str, operand_type: &str) -> Option<Overload> {
+    Some(Overload {
+        class_name: String::from(class_name),
+        method: String::from(method),
+        operand_type: TypeExpr::Named(String::from(operand_type)),
+    })
+}
+
+#[test]
+fn operators_pick_the_overload_for_the_operand_type() {
+    let overloads = resolve_operators("Note/a/ = c + Interval(); Note/b/ = c + 2; Int/n/ = 1 + 2;").unwrap();
+
+    assert_eq!(overloads, [overload("Note", "op_add", "Interval"), overload("Note", "op_add", "Int"), None]);
+}
+
+#[test]
+fn subclass_overloads_hide_the_parents() {
+    let overloads = resolve_operators("
+        class /Sharp/ extends Note { /op_add/(Int semitones)Sharp { return this; } }
+        Sharp/s/ = Sharp();
+        Note/a/ = s + 1;
+        Note/b/ = s + Interval();
+    ").unwrap();
+
+    assert_eq!(overloads, [overload("Sharp", "op_add", "Int"), overload("Sharp", "op_add", "Interval")]);
+}
+
+#[test]
+fn int_operands_widen_to_float_overloads() {
+    let overloads = resolve_operators("Gain/a/ = g * 2; Gain/b/ = g * 0.5;").unwrap();
+
+    assert_eq!(overloads, [overload("Gain", "op_mul", "Float"), overload("Gain", "op_mul", "Float")]);
+}
+
+#[test]
+fn unknown_operand_types_are_left_unresolved() {
+    let overloads = resolve_operators("Note/a/ = c + step(); Twice/t/ = Twice(); Twice/u/ = t * count;").unwrap();
+
+    assert_eq!(overloads, [None, None]);
+}
+
+#[test]
+fn operator_resolution_errors_list_the_candidates() {
+    let errors = resolve_operators("
+        Note/a/ = c - 1;
+        Note/b/ = c + \"up\";
+        Twice/t/ = Twice();
+        Twice/u/ = t * 2;
+    ").unwrap_err();
+
+    assert_eq!(errors, [
+        "Class 'Note' does not overload operator '-' (no method 'op_sub' taking one argument)",
+        "No overload of operator '+' on 'Note' takes 'String'; candidates: op_add(Interval), op_add(Int)",
+        "Ambiguous use of operator '*' on 'Twice'; candidates: op_mul(Int), op_mul(Int)",
+    ]);
+}
+
+const CALLABLES: &str = "
+    /adsr/(Float attack, Float decay = 50ms, Float release = 200ms)Envelope { return Envelope(); }
+    /chord/(Int velocity, Note... notes)Chord { return notes; }
+    class /Voice/ {
+        new(Int pitch) { }
+        static /create/(Int pitch = 60)Voice { return Voice(pitch); }
+    }
+";
+
+#[test]
+fn calls_can_mix_positional_named_and_variadic_arguments() {
+    assert!(check(&format!("{}
+        Envelope/a/ = adsr(5ms);
+        Envelope/b/ = adsr(5ms, release: 1s);
+        Envelope/c/ = adsr(release: 1s, attack: 1ms);
+        Chord/d/ = chord(90, C4, E4, G4);
+        Chord/e/ = chord(velocity: 90);
+        Voice/v/ = Voice.create(pitch: 62);
+    ", CALLABLES)).is_ok());
+}
+
+#[test]
+fn argument_errors_name_the_callable_and_parameter() {
+    let errors = check(&format!("{}
+        Envelope/a/ = adsr(1ms, 2ms, 3ms, 4ms);
+        Envelope/b/ = adsr(1ms, sustain: 0.5);
+        Envelope/c/ = adsr(1ms, attack: 2ms);
+        Chord/d/ = chord(90, notes: C4);
+        Envelope/e/ = adsr(decay: 1ms);
+        Voice/v/ = Voice();
+        Voice/w/ = Voice.create(1, 2);
+    ", CALLABLES)).unwrap_err();
+
+    assert_eq!(errors, [
+        "Too many arguments for function 'adsr': expected at most 3, got 4",
+        "No parameter named 'sustain' in function 'adsr'",
+        "Parameter 'attack' of function 'adsr' is given both positionally and by name",
+        "Variadic parameter 'notes' of function 'chord' cannot be passed by name",
+        "Missing argument for parameter 'attack' of function 'adsr'",
+        "Missing argument for parameter 'pitch' of 'Voice' constructor",
+        "Too many arguments for method 'Voice.create': expected at most 1, got 2",
+    ]);
+}
+
+#[test]
+fn every_path_of_a_typed_function_returns() {
+    let returning = [
+        "/f/(Int x)Int { if (x) { return 1; } else { return 2; } }",
+        "/f/(Int x)Int { if (x) { return 1; } return 2; }",
+        "/f/()Int { try { risky(); } catch (e) { log(e); } finally { return 0; } }",
+        "/f/()Int { try { return risky(); } catch (e) { return 0; } }",
+        "/f/(Int x)Int { match (x) { 0 => { return 1; } n => { return n; } } }",
+        "/f/(Int x)Int { match (x) { n if n > 0 => { return 1; } _ => { return 2; } } }",
+        "/f/(Int x)Int { match (x) { 0 => { return 1; } _ => { throw Error(); } } }",
+        "/f/() { log(1); return; }",
+        "Lambda/l/ = /(Int x) => x * 2;",
+    ];
+    for source in returning {
+        assert_eq!(check(source), Ok(()), "{}", source);
+    }
+}
+
+#[test]
+fn missing_and_bare_returns_are_reported() {
+    let cases = [
+        ("/f/(Int x)Int { if (x) { return 1; } }", "Function 'f' returns 'Int' but not every path ends in a return"),
+        ("/f/()Int { try { return risky(); } catch (e) { log(e); } }", "Function 'f' returns 'Int' but not every path ends in a return"),
+        ("/f/(Int x)Int { match (x) { 0 => { return 1; } 1 => { return 2; } } }", "Function 'f' returns 'Int' but not every path ends in a return"),
+        ("/f/(Int x)Int { if (x) { return; } return 1; }", "Function 'f' returns 'Int' but has a bare 'return;'"),
+        ("Lambda/l/ = /(Int x)Int { log(x); };", "Lambda returns 'Int' but not every path ends in a return"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(check(source), Err(vec![String::from(expected)]), "{}", source);
+    }
+}